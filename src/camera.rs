@@ -1,5 +1,5 @@
 use crate::player::Player;
-use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
+use bevy::prelude::*;
 
 pub struct CameraPlugin;
 
@@ -14,15 +14,8 @@ impl Plugin for CameraPlugin {
 pub struct MainCamera;
 
 pub fn spawn_camera_system(mut commands: Commands) {
-    let camera_2d = Camera2d {
-        clear_color: ClearColorConfig::Custom(Color::LIME_GREEN),
-    };
-
     commands.spawn((
-        Camera2dBundle {
-            camera_2d,
-            ..Default::default()
-        },
+        Camera2dBundle::default(),
         MainCamera,
         Name::new("Main Camera"),
     ));
@@ -0,0 +1,222 @@
+use crate::{
+    graphics::Graphics,
+    inventory::Inventory,
+    item::ItemRegistry,
+    player::Player,
+    ui::{
+        inventory_frame, inventory_frame_bundle, inventory_grid_bundle, not_typing,
+        spawn_stack_image, InventoryBox,
+    },
+};
+use bevy::prelude::*;
+
+pub struct ChestPlugin;
+
+impl Plugin for ChestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OpenChest>()
+            .add_systems(
+                Update,
+                (
+                    open_chest_system.run_if(not_typing),
+                    chest_transfer_system,
+                    quick_stack_system.run_if(not_typing),
+                ),
+            )
+            .add_systems(PostUpdate, update_chest_panel_system);
+    }
+}
+
+pub const CHEST_CAPACITY: usize = 16;
+const CHEST_COLUMNS: usize = 8;
+
+#[derive(Component)]
+pub struct Chest;
+
+/// The chest whose panel is currently open, if any.
+#[derive(Resource, Default)]
+pub struct OpenChest(pub Option<Entity>);
+
+#[derive(Component)]
+pub struct ChestPanel;
+
+#[derive(Component)]
+pub struct ChestBox(pub usize);
+
+#[derive(Component)]
+pub struct QuickStackButton;
+
+pub fn open_chest_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut open_chest: ResMut<OpenChest>,
+    player_query: Query<(&Transform, &Player)>,
+    chest_query: Query<(Entity, &Transform), With<Chest>>,
+) {
+    let (player_tf, player) = player_query.single();
+    let player_pos = player_tf.translation.truncate();
+    let distance = |tf: &Transform| tf.translation.truncate().distance(player_pos);
+
+    if let Some(chest) = open_chest.0 {
+        let in_reach = chest_query
+            .get(chest)
+            .map(|(_, tf)| distance(tf) <= player.arm_len)
+            .unwrap_or(false);
+        if !in_reach || keyboard.just_pressed(KeyCode::E) {
+            open_chest.0 = None;
+        }
+    } else if keyboard.just_pressed(KeyCode::E) {
+        open_chest.0 = chest_query
+            .iter()
+            .map(|(ent, tf)| (ent, distance(tf)))
+            .filter(|&(_, distance)| distance <= player.arm_len)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(ent, _)| ent);
+    }
+}
+
+pub fn chest_transfer_system(
+    open_chest: Res<OpenChest>,
+    inventory_box_query: Query<(&Interaction, &InventoryBox), Changed<Interaction>>,
+    chest_box_query: Query<(&Interaction, &ChestBox), Changed<Interaction>>,
+    mut player_query: Query<&mut Inventory, With<Player>>,
+    mut chest_query: Query<&mut Inventory, (With<Chest>, Without<Player>)>,
+    item_registry: Res<ItemRegistry>,
+) {
+    let Some(mut chest_inventory) = open_chest.0.and_then(|ent| chest_query.get_mut(ent).ok())
+    else {
+        return;
+    };
+    let mut inventory = player_query.single_mut();
+
+    for (interaction, inventory_box) in inventory_box_query.iter() {
+        if *interaction == Interaction::Pressed {
+            if let Err(err) =
+                inventory.move_stack(inventory_box.0, &mut chest_inventory, &item_registry)
+            {
+                info!("can't store it in the chest: {err}");
+            }
+        }
+    }
+
+    for (interaction, chest_box) in chest_box_query.iter() {
+        if *interaction == Interaction::Pressed {
+            if let Err(err) =
+                chest_inventory.move_stack(chest_box.0, &mut inventory, &item_registry)
+            {
+                info!("can't take it from the chest: {err}");
+            }
+        }
+    }
+}
+
+pub fn quick_stack_system(
+    keyboard: Res<Input<KeyCode>>,
+    open_chest: Res<OpenChest>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<QuickStackButton>)>,
+    mut player_query: Query<&mut Inventory, With<Player>>,
+    mut chest_query: Query<&mut Inventory, (With<Chest>, Without<Player>)>,
+    item_registry: Res<ItemRegistry>,
+) {
+    let pressed = keyboard.just_pressed(KeyCode::Q)
+        || button_query
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed);
+    if !pressed {
+        return;
+    }
+
+    if let Some(mut chest_inventory) = open_chest.0.and_then(|ent| chest_query.get_mut(ent).ok()) {
+        let mut inventory = player_query.single_mut();
+        inventory.quick_stack_into(&mut chest_inventory, &item_registry);
+    }
+}
+
+pub fn update_chest_panel_system(
+    mut commands: Commands,
+    open_chest: Res<OpenChest>,
+    graphics: Res<Graphics>,
+    item_registry: Res<ItemRegistry>,
+    chest_query: Query<Ref<Inventory>, With<Chest>>,
+    panel_query: Query<Entity, With<ChestPanel>>,
+) {
+    let chest_inventory = open_chest.0.and_then(|ent| chest_query.get(ent).ok());
+    let chest_changed = chest_inventory
+        .as_ref()
+        .is_some_and(|inventory| inventory.is_changed());
+    if !open_chest.is_changed() && !chest_changed && !graphics.is_changed() {
+        return;
+    }
+
+    for panel in panel_query.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
+
+    let Some(chest_inventory) = chest_inventory else {
+        return;
+    };
+
+    let (_index, size) = inventory_frame(&graphics);
+    let chest_boxes = (0..chest_inventory.capacity())
+        .map(|i| {
+            let chest_box = commands
+                .spawn((
+                    inventory_frame_bundle(&graphics),
+                    ChestBox(i),
+                    Interaction::default(),
+                ))
+                .id();
+            if let Some(stack) = chest_inventory.get(i) {
+                let ent = spawn_stack_image(&mut commands, &graphics, &item_registry, stack);
+                commands.entity(chest_box).add_child(ent);
+            }
+            chest_box
+        })
+        .collect::<Vec<_>>();
+
+    let grid = commands
+        .spawn(inventory_grid_bundle(&graphics, CHEST_COLUMNS))
+        .push_children(&chest_boxes)
+        .id();
+
+    let quick_stack_button = commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    margin: UiRect::top(Val::Px(4.0)),
+                    padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::rgba_u8(0, 0, 0, 160)),
+                ..default()
+            },
+            QuickStackButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Quick stack (Q)",
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            ));
+        })
+        .id();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    bottom: Val::Px(size.y + 16.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ChestPanel,
+            Name::new("Chest Panel"),
+        ))
+        .push_children(&[grid, quick_stack_button]);
+}
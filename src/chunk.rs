@@ -0,0 +1,171 @@
+use crate::{
+    camera::MainCamera,
+    graphics::Graphics,
+    inventory::Inventory,
+    item::ItemRegistry,
+    tile::TileRegistry,
+    world_gen::{WorldGenConfig, WorldSeed},
+    world_object::{ObjectBehaviors, ReGrowthTimer, WorldObject},
+};
+use bevy::{prelude::*, utils::HashMap};
+
+pub struct ChunkPlugin;
+
+impl Plugin for ChunkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadedChunks>()
+            .init_resource::<SavedChunks>()
+            .add_systems(Update, stream_chunks_system);
+    }
+}
+
+/// Parent of the world objects generated for one square of the map.
+#[derive(Component, Reflect)]
+pub struct Chunk(pub IVec2);
+
+#[derive(Resource, Default)]
+pub struct LoadedChunks(pub HashMap<IVec2, Entity>);
+
+/// World objects of unloaded chunks, as they were when the camera left.
+#[derive(Resource, Default)]
+pub struct SavedChunks(pub HashMap<IVec2, Vec<SavedObject>>);
+
+pub struct SavedObject {
+    pub world_object: WorldObject,
+    pub position: Vec2,
+    pub regrowth: Option<Timer>,
+    pub inventory: Option<Inventory>,
+}
+
+impl WorldGenConfig {
+    pub fn chunk_at(&self, position: Vec2) -> IVec2 {
+        (position / self.chunk_size).floor().as_ivec2()
+    }
+    pub fn chunk_area(&self, chunk: IVec2) -> Rect {
+        let min = chunk.as_vec2() * self.chunk_size;
+        Rect::from_corners(min, min + Vec2::splat(self.chunk_size))
+    }
+}
+
+/// Loads the chunks within `load_radius` of the camera's chunk and unloads those a
+/// chunk further out, so walking along a border doesn't reload it every frame.
+fn stream_chunks_system(
+    mut commands: Commands,
+    camera_query: Query<&Transform, With<MainCamera>>,
+    world_obj_query: Query<(
+        Entity,
+        &WorldObject,
+        &Transform,
+        Option<&ReGrowthTimer>,
+        Option<&Inventory>,
+        Option<&Parent>,
+    )>,
+    mut loaded: ResMut<LoadedChunks>,
+    mut saved: ResMut<SavedChunks>,
+    config: Res<WorldGenConfig>,
+    seed: Res<WorldSeed>,
+    graphics: Res<Graphics>,
+    item_registry: Res<ItemRegistry>,
+    behaviors: Res<ObjectBehaviors>,
+    tiles: Res<TileRegistry>,
+) {
+    let center = config.chunk_at(camera_query.single().translation.truncate());
+    let distance = |chunk: IVec2| (chunk - center).abs().max_element();
+
+    let unloading = loaded
+        .0
+        .keys()
+        .copied()
+        .filter(|&chunk| distance(chunk) > config.load_radius + 1)
+        .collect::<Vec<_>>();
+    if !unloading.is_empty() {
+        let chunk_entities = loaded
+            .0
+            .iter()
+            .map(|(&chunk, &ent)| (ent, chunk))
+            .collect::<HashMap<_, _>>();
+        let mut objects: HashMap<IVec2, Vec<SavedObject>> = HashMap::default();
+        for (ent, &world_object, transform, regrowth, inventory, parent) in world_obj_query.iter() {
+            let position = transform.translation.truncate();
+            // objects dropped by the player aren't children of a chunk, so go by position
+            let chunk = parent
+                .and_then(|parent| chunk_entities.get(&parent.get()).copied())
+                .unwrap_or_else(|| config.chunk_at(position));
+            if !unloading.contains(&chunk) {
+                continue;
+            }
+            objects.entry(chunk).or_default().push(SavedObject {
+                world_object,
+                position,
+                regrowth: regrowth.map(|timer| timer.0.clone()),
+                inventory: inventory.cloned(),
+            });
+            commands.entity(ent).despawn_recursive();
+        }
+        for chunk in unloading {
+            if let Some(ent) = loaded.0.remove(&chunk) {
+                commands.entity(ent).despawn_recursive();
+            }
+            saved
+                .0
+                .insert(chunk, objects.remove(&chunk).unwrap_or_default());
+        }
+    }
+
+    let radius = config.load_radius;
+    for y in center.y - radius..=center.y + radius {
+        for x in center.x - radius..=center.x + radius {
+            let chunk = IVec2::new(x, y);
+            if loaded.0.contains_key(&chunk) {
+                continue;
+            }
+
+            let world_objects = match saved.0.remove(&chunk) {
+                Some(objects) => objects
+                    .into_iter()
+                    .map(|object| {
+                        let ent = object.world_object.spawn(
+                            &mut commands,
+                            &graphics,
+                            &item_registry,
+                            &behaviors,
+                            None,
+                            Some(object.position),
+                        );
+                        if let Some(timer) = object.regrowth {
+                            commands.entity(ent).insert(ReGrowthTimer(timer));
+                        }
+                        if let Some(inventory) = object.inventory {
+                            commands.entity(ent).insert(inventory);
+                        }
+                        ent
+                    })
+                    .collect::<Vec<_>>(),
+                None => config
+                    .populate(seed.0, config.chunk_area(chunk), &tiles)
+                    .into_iter()
+                    .map(|(world_object, position)| {
+                        world_object.spawn(
+                            &mut commands,
+                            &graphics,
+                            &item_registry,
+                            &behaviors,
+                            None,
+                            Some(position),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            };
+
+            let ent = commands
+                .spawn((
+                    SpatialBundle::default(),
+                    Chunk(chunk),
+                    Name::new(format!("Chunk ({x}, {y})")),
+                ))
+                .push_children(&world_objects)
+                .id();
+            loaded.0.insert(chunk, ent);
+        }
+    }
+}
@@ -1,69 +1,903 @@
-use crate::{inventory::Inventory, ui::CraftingButton, world_object::ItemType};
-use bevy::{prelude::*, utils::HashMap};
+use crate::{
+    discovery::KnownRecipes,
+    graphics::{Graphics, GraphicsDescription},
+    inventory::{Inventory, InventoryError},
+    item::ItemRegistry,
+    planner::queue_plan,
+    player::Player,
+    ui::CraftingButton,
+    validation::{load_ron, AssetSet},
+    world_object::{ItemType, WorldObject},
+};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashMap, HashSet},
+};
+use rand::Rng;
 use serde::Deserialize;
-use std::fs;
+use std::collections::VecDeque;
 
 pub struct CraftingPlugin;
 
 impl Plugin for CraftingPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(CraftingBook::from_path("assets/crafting_book_desc.ron"))
-            .add_systems(Update, crafting_system);
+        app.insert_resource(CraftingBook::from_path(&format!(
+            "assets/{CRAFTING_BOOK_PATH}"
+        )))
+        .add_asset::<CraftingBook>()
+        .init_asset_loader::<CraftingBookLoader>()
+        .init_resource::<Surroundings>()
+        .add_systems(PreStartup, load_crafting_book_system)
+        .add_systems(
+            Update,
+            (
+                reload_crafting_book_system,
+                update_surroundings_system,
+                crafting_system,
+                cancel_crafting_system,
+                crafting_queue_system,
+            )
+                .chain(),
+        );
     }
 }
 
-#[derive(Resource, Deserialize)]
+/// Relative to the asset folder, where the asset server watches it for changes.
+pub const CRAFTING_BOOK_PATH: &str = "crafting.book.ron";
+
+/// How close a station has to be for its recipes to be craftable.
+pub const STATION_RANGE: f32 = 100.0;
+/// Jobs queued by a ctrl-click on a recipe.
+const CRAFT_MANY: usize = 5;
+/// Rows and columns of the crafting grid.
+pub const GRID_SIZE: usize = 3;
+
+/// World objects within `STATION_RANGE` of the player.
+#[derive(Resource, Default, PartialEq)]
+pub struct Surroundings(pub HashSet<WorldObject>);
+
+/// Something a recipe asks for: an exact item, or any item carrying a tag.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Ingredient {
+    Item(ItemType),
+    Tag(String),
+}
+
+impl Ingredient {
+    /// Items that satisfy the ingredient, in the order they are consumed.
+    pub fn candidates(&self, item_registry: &ItemRegistry) -> Vec<ItemType> {
+        match self {
+            Ingredient::Item(item) => vec![*item],
+            Ingredient::Tag(tag) => item_registry.tagged(tag),
+        }
+    }
+    pub fn describe(&self, item_registry: &ItemRegistry) -> String {
+        match self {
+            Ingredient::Item(item) => item_registry.get(*item).name.clone(),
+            Ingredient::Tag(tag) => format!("any {tag}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CraftBlocker {
+    MissingIngredient {
+        ingredient: Ingredient,
+        needed: usize,
+        held: usize,
+    },
+    MissingStation(WorldObject),
+}
+
+impl CraftBlocker {
+    pub fn describe(&self, item_registry: &ItemRegistry) -> String {
+        match self {
+            CraftBlocker::MissingIngredient {
+                ingredient,
+                needed,
+                held,
+            } => {
+                let name = ingredient.describe(item_registry);
+                format!("needs {needed} {name} (have {held})")
+            }
+            CraftBlocker::MissingStation(station) => {
+                format!("needs a {} nearby", station.display_name(item_registry))
+            }
+        }
+    }
+}
+
+/// Crafting jobs in progress, front first. Ingredients are taken when a job is queued.
+#[derive(Component, Default)]
+pub struct CraftingQueue {
+    pub jobs: VecDeque<CraftingJob>,
+}
+
+pub struct CraftingJob {
+    pub recipe: RecipeId,
+    pub timer: Timer,
+    /// Ingredients handed back if the job is cancelled.
+    pub refund: Vec<(ItemType, usize)>,
+    /// Products and byproducts, rolled when the job was queued.
+    pub outputs: Vec<(ItemType, usize)>,
+}
+
+impl CraftingQueue {
+    /// Hands back the refund of the job at `index` and drops it. The job stays queued if
+    /// the refund doesn't fit.
+    pub fn cancel(
+        &mut self,
+        index: usize,
+        inventory: &mut Inventory,
+        item_registry: &ItemRegistry,
+    ) -> Result<CraftingJob, InventoryError> {
+        inventory.add_many(&self.jobs[index].refund, item_registry)?;
+        Ok(self.jobs.remove(index).expect("the job was just refunded"))
+    }
+    /// Moves the outputs of the front job into the inventory once its timer has finished.
+    /// A finished job waits at the front until its outputs fit.
+    pub fn finish_front(
+        &mut self,
+        inventory: &mut Inventory,
+        item_registry: &ItemRegistry,
+    ) -> Result<Option<CraftingJob>, InventoryError> {
+        match self.jobs.front() {
+            Some(job) if job.timer.finished() => {
+                inventory.add_many(&job.outputs, item_registry)?;
+                Ok(self.jobs.pop_front())
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Resource, Deserialize, Clone, TypeUuid, TypePath)]
+#[uuid = "9c3f2a41-7d6e-4b8a-b1f5-0e2d4c6a8b93"]
 pub struct CraftingBook {
     pub craftable: Vec<CraftingRecipe>,
 }
 
+/// Names a recipe independently of where it sits in the book, so reloads can reorder it.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct RecipeId(pub String);
+
+/// Keeps the crafting book asset loaded so edits on disk reach `reload_crafting_book_system`.
+#[derive(Resource)]
+pub struct CraftingBookHandle(pub Handle<CraftingBook>);
+
+/// The asset server picks a loader by file extension alone, so every RON asset gets a
+/// double extension of its own: the book is `*.book.ron`, the graphics `*.graphics.ron`.
+#[derive(Default)]
+pub struct CraftingBookLoader;
+
+impl AssetLoader for CraftingBookLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let crafting_book: CraftingBook = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(crafting_book));
+            Ok(())
+        })
+    }
+    fn extensions(&self) -> &[&str] {
+        &["book.ron"]
+    }
+}
+
 impl CraftingBook {
+    /// Reads the book right away, before the asset server has loaded it.
     pub fn from_path(path: &str) -> Self {
-        let desc_str = fs::read_to_string(path).unwrap();
-        ron::de::from_str(&desc_str).unwrap()
+        load_ron(path)
+    }
+    pub fn get(&self, id: &RecipeId) -> Option<&CraftingRecipe> {
+        self.craftable.iter().find(|recipe| recipe.id == *id)
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
+#[serde(from = "CraftingRecipeDesc")]
 pub struct CraftingRecipe {
+    pub id: RecipeId,
     pub needed: HashMap<ItemType, usize>,
-    pub preducts: ItemType,
+    /// Counts of any items carrying the tag, on top of `needed`.
+    pub tagged: HashMap<String, usize>,
+    pub products: Vec<(ItemType, usize)>,
+    pub byproducts: Vec<Byproduct>,
+    /// World object that has to be near the player, like a workbench.
+    pub station: Option<WorldObject>,
+    /// Seconds the job spends in the crafting queue.
+    pub duration: f32,
+    pub unlock: RecipeUnlock,
+    /// Shaped recipes are only crafted by laying this out in the crafting grid.
+    pub pattern: Option<Pattern>,
+}
+
+/// Items laid out in the crafting grid, `None` for an empty cell. Empty rows and
+/// columns around the items are trimmed, so a pattern matches anywhere in the grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern(pub Vec<Vec<Option<ItemType>>>);
+
+impl Pattern {
+    pub fn new(rows: Vec<Vec<Option<ItemType>>>) -> Self {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let cell = |row: usize, col: usize| rows[row].get(col).copied().flatten();
+        let filled_rows = (0..rows.len())
+            .filter(|&row| (0..width).any(|col| cell(row, col).is_some()))
+            .collect::<Vec<_>>();
+        let filled_cols = (0..width)
+            .filter(|&col| (0..rows.len()).any(|row| cell(row, col).is_some()))
+            .collect::<Vec<_>>();
+        let (Some(&top), Some(&bottom), Some(&left), Some(&right)) = (
+            filled_rows.first(),
+            filled_rows.last(),
+            filled_cols.first(),
+            filled_cols.last(),
+        ) else {
+            return Pattern(Vec::new());
+        };
+        Pattern(
+            (top..=bottom)
+                .map(|row| (left..=right).map(|col| cell(row, col)).collect())
+                .collect(),
+        )
+    }
+    pub fn width(&self) -> usize {
+        self.0.first().map_or(0, Vec::len)
+    }
+    pub fn height(&self) -> usize {
+        self.0.len()
+    }
+    /// How many of each item the pattern takes.
+    pub fn counts(&self) -> HashMap<ItemType, usize> {
+        let mut counts = HashMap::default();
+        for &item in self.0.iter().flatten().flatten() {
+            *counts.entry(item).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// How a recipe gets into the player's `KnownRecipes`.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RecipeUnlock {
+    /// Picking up any of its ingredients.
+    #[default]
+    Ingredient,
+    /// Reading a scroll.
+    Scroll,
+    /// Talking to an NPC.
+    Npc,
+    /// Known from the start.
+    Known,
+}
+
+/// An extra output that only turns up some of the time.
+#[derive(Deserialize, Clone, Copy)]
+pub struct Byproduct {
+    pub item: ItemType,
+    pub amount: usize,
+    /// Probability in `0.0..=1.0`.
+    pub chance: f32,
+}
+
+/// On-disk shape of a recipe. Older books name a single product in `preducts`.
+#[derive(Deserialize)]
+#[serde(rename = "CraftingRecipe")]
+struct CraftingRecipeDesc {
+    /// Defaults to the main product's name.
+    #[serde(default)]
+    id: RecipeId,
+    #[serde(default)]
+    needed: HashMap<ItemType, usize>,
+    #[serde(default)]
+    tagged: HashMap<String, usize>,
+    #[serde(default)]
+    products: Vec<(ItemType, usize)>,
+    #[serde(default)]
+    preducts: ItemType,
+    #[serde(default)]
+    byproducts: Vec<Byproduct>,
+    #[serde(default)]
+    station: Option<WorldObject>,
+    #[serde(default)]
+    duration: f32,
+    #[serde(default)]
+    unlock: RecipeUnlock,
+    /// Rows of the grid, `None` for an empty cell. Replaces `needed`.
+    #[serde(default)]
+    pattern: Vec<Vec<ItemType>>,
+}
+
+impl From<CraftingRecipeDesc> for CraftingRecipe {
+    fn from(desc: CraftingRecipeDesc) -> Self {
+        let mut products = desc.products;
+        if desc.preducts != ItemType::None {
+            products.insert(0, (desc.preducts, 1));
+        }
+        let id = if desc.id.0.is_empty() {
+            let main_product = products.first().map(|&(item, _)| item).unwrap_or_default();
+            RecipeId(format!("{main_product:?}").to_lowercase())
+        } else {
+            desc.id
+        };
+        let pattern = (!desc.pattern.is_empty()).then(|| {
+            Pattern::new(
+                desc.pattern
+                    .into_iter()
+                    .map(|row| {
+                        row.into_iter()
+                            .map(|item| (item != ItemType::None).then_some(item))
+                            .collect()
+                    })
+                    .collect(),
+            )
+        });
+        let needed = pattern.as_ref().map_or(desc.needed, Pattern::counts);
+        CraftingRecipe {
+            id,
+            needed,
+            tagged: desc.tagged,
+            products,
+            byproducts: desc.byproducts,
+            station: desc.station,
+            duration: desc.duration,
+            unlock: desc.unlock,
+            pattern,
+        }
+    }
 }
 
 impl CraftingRecipe {
-    pub fn can_craft(&self, inventory: &Inventory) -> bool {
-        self.needed
+    /// The product shown on the recipe's button.
+    pub fn main_product(&self) -> ItemType {
+        self.products
+            .first()
+            .map(|&(item, _)| item)
+            .unwrap_or_default()
+    }
+    pub fn check(
+        &self,
+        inventory: &Inventory,
+        surroundings: &Surroundings,
+        item_registry: &ItemRegistry,
+    ) -> Result<(), CraftBlocker> {
+        if let Some(station) = self.station {
+            if !surroundings.0.contains(&station) {
+                return Err(CraftBlocker::MissingStation(station));
+            }
+        }
+        self.resolve(inventory, item_registry, 1).map(|_| ())
+    }
+    pub fn can_craft(
+        &self,
+        inventory: &Inventory,
+        surroundings: &Surroundings,
+        item_registry: &ItemRegistry,
+    ) -> bool {
+        self.check(inventory, surroundings, item_registry).is_ok()
+    }
+    /// How many times the held ingredients cover the recipe, ignoring the station.
+    pub fn max_craftable(&self, inventory: &Inventory, item_registry: &ItemRegistry) -> usize {
+        let requirements = self.requirements();
+        let upper_bound = requirements
+            .iter()
+            .map(|(ingredient, amount)| {
+                let held: usize = ingredient
+                    .candidates(item_registry)
+                    .into_iter()
+                    .map(|item| inventory.count(item))
+                    .sum();
+                held / amount
+            })
+            .min()
+            .unwrap_or(0);
+        // exact and tagged ingredients can compete for the same items, so search below the
+        // bound; whatever can be crafted n times can also be crafted fewer times
+        let (mut craftable, mut uncraftable) = (0, upper_bound + 1);
+        while uncraftable - craftable > 1 {
+            let times = craftable + (uncraftable - craftable) / 2;
+            if self.resolve(inventory, item_registry, times).is_ok() {
+                craftable = times;
+            } else {
+                uncraftable = times;
+            }
+        }
+        craftable
+    }
+    /// Exact items first, then tags, each group in a fixed order.
+    pub fn requirements(&self) -> Vec<(Ingredient, usize)> {
+        let mut requirements = self
+            .needed
+            .iter()
+            .map(|(&item, &amount)| (Ingredient::Item(item), amount))
+            .chain(
+                self.tagged
+                    .iter()
+                    .map(|(tag, &amount)| (Ingredient::Tag(tag.clone()), amount)),
+            )
+            .filter(|&(_, amount)| amount > 0)
+            .collect::<Vec<_>>();
+        requirements.sort();
+        requirements
+    }
+    /// Whether `item` counts towards any of the recipe's ingredients.
+    pub fn uses(&self, item: ItemType, item_registry: &ItemRegistry) -> bool {
+        self.needed.contains_key(&item)
+            || item_registry
+                .get(item)
+                .tags
+                .iter()
+                .any(|tag| self.tagged.contains_key(tag))
+    }
+    /// Picks the concrete items `times` crafts consume. Requirements are served in
+    /// `requirements` order and a tag takes its items in `ItemType` order, so the same
+    /// inventory always gives the same choice.
+    pub fn resolve(
+        &self,
+        inventory: &Inventory,
+        item_registry: &ItemRegistry,
+        times: usize,
+    ) -> Result<Vec<(ItemType, usize)>, CraftBlocker> {
+        let mut reserved: HashMap<ItemType, usize> = HashMap::default();
+        let mut chosen = Vec::new();
+        for (ingredient, amount) in self.requirements() {
+            let needed = amount * times;
+            let free = ingredient
+                .candidates(item_registry)
+                .into_iter()
+                .map(|item| {
+                    let taken = reserved.get(&item).copied().unwrap_or(0);
+                    (item, inventory.count(item).saturating_sub(taken))
+                })
+                .collect::<Vec<_>>();
+            let held = free.iter().map(|&(_, count)| count).sum();
+            if held < needed {
+                return Err(CraftBlocker::MissingIngredient {
+                    ingredient,
+                    needed,
+                    held,
+                });
+            }
+
+            let mut remaining = needed;
+            for (item, count) in free {
+                let taken = remaining.min(count);
+                if taken > 0 {
+                    *reserved.entry(item).or_default() += taken;
+                    chosen.push((item, taken));
+                    remaining -= taken;
+                }
+            }
+        }
+        Ok(chosen)
+    }
+    /// The products plus whichever byproducts turn up this time.
+    pub fn roll_outputs(&self) -> Vec<(ItemType, usize)> {
+        self.products
             .iter()
-            .all(|(item, cnt)| *inventory.items.get(item).get_or_insert(&0) >= cnt)
+            .copied()
+            .chain(self.roll_byproducts())
+            .collect()
+    }
+    pub fn roll_byproducts(&self) -> Vec<(ItemType, usize)> {
+        let mut rng = rand::thread_rng();
+        self.byproducts
+            .iter()
+            .filter(|byproduct| rng.gen::<f32>() < byproduct.chance)
+            .map(|byproduct| (byproduct.item, byproduct.amount))
+            .collect()
     }
 }
 
-pub fn cost_and_craft(inventory: &mut Inventory, recipe: &CraftingRecipe) {
-    recipe.needed.iter().for_each(|(&item, &cnt)| {
-        inventory.cost(item, cnt);
+/// Takes the ingredients and queues the job, or leaves the inventory untouched.
+pub fn queue_craft(
+    inventory: &mut Inventory,
+    queue: &mut CraftingQueue,
+    recipe: &CraftingRecipe,
+    item_registry: &ItemRegistry,
+) -> Result<(), CraftBlocker> {
+    let ingredients = recipe.resolve(inventory, item_registry, 1)?;
+    inventory
+        .try_remove_many(&ingredients)
+        .expect("resolved ingredients are held");
+    queue.jobs.push_back(CraftingJob {
+        recipe: recipe.id.clone(),
+        timer: Timer::from_seconds(recipe.duration, TimerMode::Once),
+        refund: ingredients,
+        outputs: recipe.roll_outputs(),
     });
-    inventory.add(recipe.preducts, 1);
+    Ok(())
 }
 
+/// Click queues one job, ctrl-click up to `CRAFT_MANY` and shift-click as many as affordable.
+/// Alt-click crafts the missing intermediates too, as a single job.
 fn crafting_system(
+    keyboard: Res<Input<KeyCode>>,
     interaction_query: Query<
         (&Interaction, &CraftingButton),
         (Changed<Interaction>, With<CraftingButton>),
     >,
     crafting_book: Res<CraftingBook>,
-    mut inventory_query: Query<&mut Inventory>,
+    surroundings: Res<Surroundings>,
+    item_registry: Res<ItemRegistry>,
+    mut player_query: Query<(&mut Inventory, &mut CraftingQueue, &KnownRecipes), With<Player>>,
 ) {
     for (interaction, crafting_button) in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
-            let crafting_index = crafting_button.0;
-            let recipe = &crafting_book.craftable[crafting_index];
-            let mut inventory = inventory_query.single_mut();
-            if recipe.can_craft(&inventory) {
-                info!("crafted: {:?} × 1", recipe.preducts);
-                cost_and_craft(&mut inventory, recipe);
+            let Some(recipe) = crafting_book.get(&crafting_button.0) else {
+                continue;
+            };
+            let (mut inventory, mut queue, known) = player_query.single_mut();
+            let name = &item_registry.get(recipe.main_product()).name;
+
+            if keyboard.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+                let usable = |recipe: &CraftingRecipe| {
+                    known.0.contains(&recipe.id)
+                        && recipe.pattern.is_none()
+                        && recipe
+                            .station
+                            .is_none_or(|station| surroundings.0.contains(&station))
+                };
+                let plan = match crafting_book.plan(
+                    recipe.main_product(),
+                    1,
+                    &inventory,
+                    &item_registry,
+                    &usable,
+                ) {
+                    Ok(plan) => plan,
+                    Err(err) => {
+                        info!("can't plan the {name}: {err}");
+                        continue;
+                    }
+                };
+                if !plan.is_reachable() {
+                    let missing = plan
+                        .missing
+                        .iter()
+                        .map(|(ingredient, amount)| {
+                            format!("{amount} {}", ingredient.describe(&item_registry))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    info!("can't plan the {name}: missing {missing}");
+                    continue;
+                }
+                match queue_plan(&mut inventory, &mut queue, &crafting_book, &plan) {
+                    Ok(()) => info!("queued: {name} in {} steps", plan.steps.len()),
+                    Err(err) => info!("can't craft the {name}: {err}"),
+                }
+                continue;
+            }
+
+            if let Err(blocker) = recipe.check(&inventory, &surroundings, &item_registry) {
+                info!(
+                    "can't craft the {name}: {}",
+                    blocker.describe(&item_registry)
+                );
+                continue;
+            }
+
+            let max = recipe.max_craftable(&inventory, &item_registry);
+            let amount = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+                max
+            } else if keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+                max.min(CRAFT_MANY)
             } else {
-                info!("not enough to crafting the {:?}", recipe.preducts);
+                1
+            };
+            let mut queued = 0;
+            for _ in 0..amount {
+                if let Err(blocker) =
+                    queue_craft(&mut inventory, &mut queue, recipe, &item_registry)
+                {
+                    info!(
+                        "can't craft the {name}: {}",
+                        blocker.describe(&item_registry)
+                    );
+                    break;
+                }
+                queued += 1;
+            }
+            if queued > 0 {
+                info!("queued: {name} × {queued}");
+            }
+        }
+    }
+}
+
+/// Right-clicking a recipe cancels its most recently queued job.
+fn cancel_crafting_system(
+    mouse_button: Res<Input<MouseButton>>,
+    interaction_query: Query<(&Interaction, &CraftingButton)>,
+    item_registry: Res<ItemRegistry>,
+    mut player_query: Query<(&mut Inventory, &mut CraftingQueue), With<Player>>,
+) {
+    if !mouse_button.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Some((_, crafting_button)) = interaction_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Hovered)
+    else {
+        return;
+    };
+
+    let (mut inventory, mut queue) = player_query.single_mut();
+    let Some(job_index) = queue
+        .jobs
+        .iter()
+        .rposition(|job| job.recipe == crafting_button.0)
+    else {
+        return;
+    };
+
+    if let Err(err) = queue.cancel(job_index, &mut inventory, &item_registry) {
+        info!("can't cancel the craft: {err}");
+    }
+}
+
+fn crafting_queue_system(
+    time: Res<Time>,
+    item_registry: Res<ItemRegistry>,
+    mut player_query: Query<(&mut Inventory, &mut CraftingQueue), With<Player>>,
+) {
+    let (mut inventory, mut queue) = player_query.single_mut();
+    let Some(job) = queue.jobs.front_mut() else {
+        return;
+    };
+
+    job.timer.tick(time.delta());
+    let just_finished = job.timer.just_finished();
+
+    match queue.finish_front(&mut inventory, &item_registry) {
+        Ok(Some(job)) => {
+            for &(item, amount) in job.outputs.iter() {
+                info!("crafted: {} × {amount}", item_registry.get(item).name);
             }
         }
+        Ok(None) => {}
+        Err(err) if just_finished => info!("crafting is waiting: {err}"),
+        Err(_) => {}
+    }
+}
+
+fn load_crafting_book_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CraftingBookHandle(asset_server.load(CRAFTING_BOOK_PATH)));
+}
+
+/// Swaps in the edited book once it validates against the current graphics.
+fn reload_crafting_book_system(
+    mut book_events: EventReader<AssetEvent<CraftingBook>>,
+    book_assets: Res<Assets<CraftingBook>>,
+    book_handle: Res<CraftingBookHandle>,
+    desc_assets: Res<Assets<GraphicsDescription>>,
+    graphics: Res<Graphics>,
+    assets: AssetSet,
+    mut crafting_book: ResMut<CraftingBook>,
+) {
+    for event in book_events.iter() {
+        let AssetEvent::Modified { handle } = event else {
+            continue;
+        };
+        let Some(edited) = book_assets.get(handle).filter(|_| *handle == book_handle.0) else {
+            continue;
+        };
+        let Some(graphics_desc) = desc_assets.get(&graphics.graphics_desc) else {
+            warn!("{CRAFTING_BOOK_PATH} not reloaded: graphics are still loading");
+            continue;
+        };
+        if !assets.accepts(edited, graphics_desc, CRAFTING_BOOK_PATH) {
+            continue;
+        }
+
+        *crafting_book = edited.clone();
+        info!("reloaded {CRAFTING_BOOK_PATH}");
+    }
+}
+
+fn update_surroundings_system(
+    player_query: Query<&GlobalTransform, With<Player>>,
+    world_obj_query: Query<(&WorldObject, &GlobalTransform)>,
+    mut surroundings: ResMut<Surroundings>,
+) {
+    let player_pos = player_query.single().translation().truncate();
+    let nearby = Surroundings(
+        world_obj_query
+            .iter()
+            .filter(|(_, tf)| tf.translation().truncate().distance(player_pos) <= STATION_RANGE)
+            .map(|(&world_object, _)| world_object)
+            .collect(),
+    );
+    if *surroundings != nearby {
+        *surroundings = nearby;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{inventory::test_inventory, item::test_registry};
+    use std::time::Duration;
+
+    fn recipe(desc: &str) -> CraftingRecipe {
+        ron::de::from_str(desc).unwrap()
+    }
+
+    #[test]
+    fn tags_take_their_items_in_item_type_order() {
+        let item_registry = test_registry();
+        let recipe = recipe(r#"CraftingRecipe(tagged: {"fuel": 3}, products: [(Stone, 1)])"#);
+        let inventory = test_inventory(&[(ItemType::Fire, 5), (ItemType::Wood, 2)], &item_registry);
+
+        assert_eq!(
+            recipe.resolve(&inventory, &item_registry, 1),
+            Ok(vec![(ItemType::Wood, 2), (ItemType::Fire, 1)])
+        );
+    }
+
+    #[test]
+    fn exact_items_are_served_before_tags() {
+        let item_registry = test_registry();
+        let recipe = recipe(
+            r#"CraftingRecipe(needed: {Wood: 1}, tagged: {"fuel": 2}, products: [(Stone, 1)])"#,
+        );
+        let inventory = test_inventory(&[(ItemType::Wood, 2), (ItemType::Fire, 5)], &item_registry);
+
+        assert_eq!(
+            recipe.resolve(&inventory, &item_registry, 1),
+            Ok(vec![
+                (ItemType::Wood, 1),
+                (ItemType::Wood, 1),
+                (ItemType::Fire, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn a_short_tag_counts_every_item_carrying_it() {
+        let item_registry = test_registry();
+        let recipe = recipe(r#"CraftingRecipe(tagged: {"fuel": 4}, products: [(Stone, 1)])"#);
+        let inventory = test_inventory(&[(ItemType::Wood, 1), (ItemType::Fire, 2)], &item_registry);
+
+        assert_eq!(
+            recipe.resolve(&inventory, &item_registry, 1),
+            Err(CraftBlocker::MissingIngredient {
+                ingredient: Ingredient::Tag("fuel".to_string()),
+                needed: 4,
+                held: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn max_craftable_counts_items_shared_by_an_exact_and_a_tagged_ingredient_once() {
+        let item_registry = test_registry();
+        let recipe = recipe(
+            r#"CraftingRecipe(needed: {Wood: 1}, tagged: {"fuel": 1}, products: [(Stone, 1)])"#,
+        );
+
+        let only_wood = test_inventory(&[(ItemType::Wood, 3)], &item_registry);
+        assert_eq!(recipe.max_craftable(&only_wood, &item_registry), 1);
+
+        let with_fire = test_inventory(&[(ItemType::Wood, 3), (ItemType::Fire, 2)], &item_registry);
+        assert_eq!(recipe.max_craftable(&with_fire, &item_registry), 2);
+
+        let plenty = test_inventory(&[(ItemType::Wood, 10), (ItemType::Fire, 7)], &item_registry);
+        assert_eq!(recipe.max_craftable(&plenty, &item_registry), 8);
+
+        let empty = Inventory::new();
+        assert_eq!(recipe.max_craftable(&empty, &item_registry), 0);
+    }
+
+    #[test]
+    fn old_books_with_preducts_still_load() {
+        let recipe = recipe("CraftingRecipe(needed: {Wood: 1}, preducts: Fire)");
+
+        assert_eq!(recipe.products, [(ItemType::Fire, 1)]);
+        assert_eq!(recipe.id, RecipeId("fire".to_string()));
+        assert_eq!(recipe.needed, HashMap::from_iter([(ItemType::Wood, 1)]));
+    }
+
+    #[test]
+    fn preducts_comes_before_the_listed_products() {
+        let recipe =
+            recipe("CraftingRecipe(needed: {Wood: 1}, preducts: Fire, products: [(Stones, 2)])");
+
+        assert_eq!(
+            recipe.products,
+            [(ItemType::Fire, 1), (ItemType::Stones, 2)]
+        );
+        assert_eq!(recipe.id, RecipeId("fire".to_string()));
+    }
+
+    #[test]
+    fn cancelling_refunds_exactly_the_resolved_ingredients() {
+        let item_registry = test_registry();
+        let recipe = recipe(r#"CraftingRecipe(tagged: {"fuel": 3}, products: [(Stone, 1)])"#);
+        let mut inventory =
+            test_inventory(&[(ItemType::Wood, 2), (ItemType::Fire, 5)], &item_registry);
+        let mut queue = CraftingQueue::default();
+
+        queue_craft(&mut inventory, &mut queue, &recipe, &item_registry).unwrap();
+        assert_eq!(
+            queue.jobs[0].refund,
+            [(ItemType::Wood, 2), (ItemType::Fire, 1)]
+        );
+        assert_eq!(inventory.count(ItemType::Fire), 4);
+
+        queue.cancel(0, &mut inventory, &item_registry).unwrap();
+        assert!(queue.jobs.is_empty());
+        assert_eq!(inventory.count(ItemType::Wood), 2);
+        assert_eq!(inventory.count(ItemType::Fire), 5);
+    }
+
+    #[test]
+    fn a_refund_that_does_not_fit_leaves_the_job_queued() {
+        let item_registry = test_registry();
+        let recipe = recipe("CraftingRecipe(needed: {Wood: 1}, products: [(Stone, 1)])");
+        let mut inventory = Inventory::with_capacity(1);
+        inventory.add(ItemType::Wood, 1, &item_registry).unwrap();
+        let mut queue = CraftingQueue::default();
+
+        queue_craft(&mut inventory, &mut queue, &recipe, &item_registry).unwrap();
+        inventory.add(ItemType::Fire, 10, &item_registry).unwrap();
+
+        assert_eq!(
+            queue.cancel(0, &mut inventory, &item_registry).err(),
+            Some(InventoryError::Full {
+                item: ItemType::Wood,
+                overflow: 1,
+            })
+        );
+        assert_eq!(queue.jobs.len(), 1);
+        assert_eq!(inventory.count(ItemType::Fire), 10);
+    }
+
+    #[test]
+    fn a_finished_job_waits_at_the_front_until_its_outputs_fit() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(1);
+        inventory.add(ItemType::Stone, 10, &item_registry).unwrap();
+        let mut queue = CraftingQueue::default();
+        for recipe in ["fire", "stones"] {
+            queue.jobs.push_back(CraftingJob {
+                recipe: RecipeId(recipe.to_string()),
+                timer: Timer::from_seconds(1.0, TimerMode::Once),
+                refund: Vec::new(),
+                outputs: vec![(ItemType::Fire, 1)],
+            });
+        }
+
+        assert!(matches!(
+            queue.finish_front(&mut inventory, &item_registry),
+            Ok(None)
+        ));
+
+        queue.jobs[0].timer.tick(Duration::from_secs(1));
+        assert_eq!(
+            queue.finish_front(&mut inventory, &item_registry).err(),
+            Some(InventoryError::Full {
+                item: ItemType::Fire,
+                overflow: 1,
+            })
+        );
+        assert_eq!(queue.jobs.len(), 2);
+        assert_eq!(queue.jobs[0].recipe, RecipeId("fire".to_string()));
+
+        inventory.take(0);
+        let finished = queue.finish_front(&mut inventory, &item_registry).unwrap();
+        assert_eq!(
+            finished.map(|job| job.recipe),
+            Some(RecipeId("fire".to_string()))
+        );
+        assert_eq!(inventory.count(ItemType::Fire), 1);
+        assert_eq!(queue.jobs.len(), 1);
     }
 }
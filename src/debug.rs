@@ -1,5 +1,6 @@
-use crate::inventory::Inventory;
-use crate::player::Player;
+use crate::chunk::Chunk;
+use crate::inventory::{Inventory, ItemAdded, ItemData, ItemRemoved, ItemStack};
+use crate::player::{EquippedItem, Player};
 use crate::world_object::Pickupable;
 use bevy::input::common_conditions::input_toggle_active;
 use bevy::prelude::*;
@@ -13,7 +14,24 @@ impl Plugin for DebugPlugin {
             WorldInspectorPlugin::default().run_if(input_toggle_active(true, KeyCode::Escape)),
         )
         .register_type::<Player>()
+        .register_type::<EquippedItem>()
         .register_type::<Pickupable>()
-        .register_type::<Inventory>();
+        .register_type::<Inventory>()
+        .register_type::<ItemStack>()
+        .register_type::<ItemData>()
+        .register_type::<Chunk>()
+        .add_systems(Update, log_inventory_events_system);
+    }
+}
+
+fn log_inventory_events_system(
+    mut added_events: EventReader<ItemAdded>,
+    mut removed_events: EventReader<ItemRemoved>,
+) {
+    for event in added_events.iter() {
+        debug!("{:?} + {:?} × {}", event.entity, event.item, event.amount);
+    }
+    for event in removed_events.iter() {
+        debug!("{:?} - {:?} × {}", event.entity, event.item, event.amount);
     }
 }
@@ -0,0 +1,144 @@
+use crate::{
+    crafting::{CraftingBook, CraftingRecipe, RecipeId, RecipeUnlock},
+    inventory::{Inventory, ItemAdded},
+    item::ItemRegistry,
+    npc::Npc,
+    player::{EquippedItem, Player},
+    ui::not_typing,
+    world_object::ItemType,
+};
+use bevy::{prelude::*, utils::HashSet};
+
+pub struct DiscoveryPlugin;
+
+impl Plugin for DiscoveryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                refresh_known_recipes_system,
+                discover_by_ingredient_system,
+                (read_scroll_system, talk_to_npc_system).run_if(not_typing),
+            ),
+        );
+    }
+}
+
+const TALK_RANGE: f32 = 80.0;
+
+/// Recipes the player has discovered.
+#[derive(Component, Default)]
+pub struct KnownRecipes(pub HashSet<RecipeId>);
+
+impl KnownRecipes {
+    pub fn starting(crafting_book: &CraftingBook) -> Self {
+        KnownRecipes(
+            crafting_book
+                .craftable
+                .iter()
+                .filter(|recipe| recipe.unlock == RecipeUnlock::Known)
+                .map(|recipe| recipe.id.clone())
+                .collect(),
+        )
+    }
+    /// Locked recipes that unlock the given way, in book order.
+    fn locked<'a>(
+        &self,
+        crafting_book: &'a CraftingBook,
+        unlock: RecipeUnlock,
+    ) -> Vec<&'a CraftingRecipe> {
+        crafting_book
+            .craftable
+            .iter()
+            .filter(|recipe| recipe.unlock == unlock && !self.0.contains(&recipe.id))
+            .collect()
+    }
+    fn learn(&mut self, recipe: &CraftingRecipe, item_registry: &ItemRegistry) {
+        let product = recipe.main_product();
+        info!("learned to craft the {}", item_registry.get(product).name);
+        self.0.insert(recipe.id.clone());
+    }
+}
+
+/// Drops recipes a reloaded book no longer has and picks up new starting ones.
+fn refresh_known_recipes_system(
+    mut player_query: Query<&mut KnownRecipes, With<Player>>,
+    crafting_book: Res<CraftingBook>,
+) {
+    if !crafting_book.is_changed() || crafting_book.is_added() {
+        return;
+    }
+    let mut known = player_query.single_mut();
+    known.0.retain(|id| crafting_book.get(id).is_some());
+    known.0.extend(KnownRecipes::starting(&crafting_book).0);
+}
+
+fn discover_by_ingredient_system(
+    mut added_events: EventReader<ItemAdded>,
+    mut player_query: Query<(Entity, &mut KnownRecipes), With<Player>>,
+    crafting_book: Res<CraftingBook>,
+    item_registry: Res<ItemRegistry>,
+) {
+    let (player, mut known) = player_query.single_mut();
+    for event in added_events.iter().filter(|event| event.entity == player) {
+        let discovered = known.locked(&crafting_book, RecipeUnlock::Ingredient);
+        for recipe in discovered {
+            if recipe.uses(event.item, &item_registry) {
+                known.learn(recipe, &item_registry);
+            }
+        }
+    }
+}
+
+/// Pressing R with a scroll in hand reads it and teaches the next scroll recipe.
+fn read_scroll_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut player_query: Query<(&mut KnownRecipes, &mut Inventory, &EquippedItem), With<Player>>,
+    crafting_book: Res<CraftingBook>,
+    item_registry: Res<ItemRegistry>,
+) {
+    if !keyboard.just_pressed(KeyCode::R) {
+        return;
+    }
+    let (mut known, mut inventory, equipped) = player_query.single_mut();
+    if equipped.item != Some(ItemType::Scroll) {
+        return;
+    }
+
+    let Some(&recipe) = known.locked(&crafting_book, RecipeUnlock::Scroll).first() else {
+        info!("the scroll has nothing new to teach");
+        return;
+    };
+    if inventory.cost(ItemType::Scroll, 1).is_ok() {
+        known.learn(recipe, &item_registry);
+    }
+}
+
+/// Pressing T next to an NPC teaches every recipe NPCs know.
+fn talk_to_npc_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut player_query: Query<(&Transform, &mut KnownRecipes), With<Player>>,
+    npc_query: Query<&Transform, With<Npc>>,
+    crafting_book: Res<CraftingBook>,
+    item_registry: Res<ItemRegistry>,
+) {
+    if !keyboard.just_pressed(KeyCode::T) {
+        return;
+    }
+    let (player_tf, mut known) = player_query.single_mut();
+    let player_pos = player_tf.translation.truncate();
+    let in_range = npc_query
+        .iter()
+        .any(|npc_tf| npc_tf.translation.truncate().distance(player_pos) <= TALK_RANGE);
+    if !in_range {
+        return;
+    }
+
+    let taught = known.locked(&crafting_book, RecipeUnlock::Npc);
+    if taught.is_empty() {
+        info!("the villager has nothing new to teach");
+    }
+    for recipe in taught {
+        known.learn(recipe, &item_registry);
+    }
+}
@@ -1,9 +1,14 @@
 use crate::{
     camera::MainCamera,
+    chest::OpenChest,
     graphics::Graphics,
+    grid_crafting::{CraftingGrid, GridBox},
     inventory::Inventory,
+    item::ItemRegistry,
     player::Player,
-    world_object::{ItemType, WorldObject},
+    tile::Ground,
+    ui::InventoryBox,
+    world_object::{ItemType, ObjectBehaviors, WorldObject},
 };
 use bevy::{prelude::*, window::PrimaryWindow};
 
@@ -11,27 +16,42 @@ pub struct DragPlugin;
 
 impl Plugin for DragPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (hoverable_system, draggable_system))
-            .add_systems(PostUpdate, drop_system);
+        app.add_systems(
+            Update,
+            (hoverable_system, draggable_system, drag_ghost_system),
+        )
+        .add_systems(PostUpdate, drop_system);
     }
 }
 
+const PLACEMENT_GRID: f32 = 16.0;
+const DRAG_GHOST_SIZE: f32 = 32.0;
+
 #[derive(Component)]
 pub struct Hoverable;
 
 #[derive(Component)]
 pub struct Hovered;
 
-#[derive(Component)]
-pub struct Dragged;
+/// The item picked up from the stack and how many of it. The slot may hold something
+/// else by the time it's dropped.
+#[derive(Component, Clone, Copy)]
+pub struct Dragged {
+    pub item: ItemType,
+    pub count: usize,
+}
 
+/// An item image in the hotbar, pointing at its inventory slot.
 #[derive(Component)]
 pub struct Draggable {
-    pub item_type: ItemType,
+    pub slot: usize,
 }
 
 #[derive(Component)]
-pub struct Dropped;
+pub struct Dropped(pub Dragged);
+
+#[derive(Component)]
+pub struct DragGhost;
 
 fn hoverable_system(
     mut commands: Commands,
@@ -60,48 +80,170 @@ fn hoverable_system(
 fn draggable_system(
     mut commands: Commands,
     i_mouse_button: Res<Input<MouseButton>>,
-    q_pressed: Query<Entity, (With<Hovered>, With<Draggable>)>,
-    q_released: Query<Entity, With<Dragged>>,
+    keyboard: Res<Input<KeyCode>>,
+    q_pressed: Query<(Entity, &Draggable), With<Hovered>>,
+    q_released: Query<(Entity, &Dragged)>,
+    mut player_query: Query<&mut Inventory, With<Player>>,
+    item_registry: Res<ItemRegistry>,
+    open_chest: Res<OpenChest>,
 ) {
-    if i_mouse_button.just_pressed(MouseButton::Left) {
-        if let Some(entity) = q_pressed.iter().next() {
-            commands.entity(entity).insert(Dragged);
+    // clicks on the hotbar move items into the chest while it is open
+    if open_chest.0.is_none() {
+        if let Some((entity, draggable)) = q_pressed.iter().next() {
+            let mut inventory = player_query.single_mut();
+            let stack = inventory
+                .get(draggable.slot)
+                .map(|stack| (stack.item, stack.count));
+            let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+            if i_mouse_button.just_pressed(MouseButton::Left) && shift {
+                if let Some(empty_slot) = inventory.empty_slot() {
+                    inventory.move_within(draggable.slot, empty_slot, 1, &item_registry);
+                }
+            } else if let Some((item, count)) = stack {
+                if i_mouse_button.just_pressed(MouseButton::Left) {
+                    commands.entity(entity).insert(Dragged { item, count });
+                } else if i_mouse_button.just_pressed(MouseButton::Right) {
+                    let count = count.div_ceil(2);
+                    commands.entity(entity).insert(Dragged { item, count });
+                }
+            }
         }
-    } else if i_mouse_button.just_released(MouseButton::Left) {
-        for entity in q_released.iter() {
+    }
+
+    if i_mouse_button.any_just_released([MouseButton::Left, MouseButton::Right]) {
+        for (entity, dragged) in q_released.iter() {
             commands.entity(entity).remove::<Dragged>();
-            commands.entity(entity).insert(Dropped);
+            commands.entity(entity).insert(Dropped(*dragged));
         }
     }
 }
 
+fn drag_ghost_system(
+    mut commands: Commands,
+    windows_query: Query<&Window, With<PrimaryWindow>>,
+    dragged_query: Query<&Dragged>,
+    mut ghost_query: Query<(Entity, &mut Style), With<DragGhost>>,
+    graphics: Res<Graphics>,
+) {
+    let cursor = windows_query.single().cursor_position();
+    let dragged_item = dragged_query.iter().next().map(|dragged| dragged.item);
+
+    match (dragged_item, ghost_query.get_single_mut()) {
+        (Some(_), Ok((_, mut style))) => {
+            if let Some(cursor) = cursor {
+                style.left = Val::Px(cursor.x - DRAG_GHOST_SIZE / 2.0);
+                style.top = Val::Px(cursor.y - DRAG_GHOST_SIZE / 2.0);
+            }
+        }
+        (Some(item), Err(_)) => {
+            let (index, _size) = *graphics
+                .item_index_map
+                .get(&WorldObject::Item(item))
+                .unwrap_or_else(|| panic!("drag ghost [{:?}] index not found", item));
+            let cursor = cursor.unwrap_or_default();
+
+            commands.spawn((
+                AtlasImageBundle {
+                    texture_atlas: graphics.texture_altas.clone(),
+                    texture_atlas_image: UiTextureAtlasImage {
+                        index,
+                        ..Default::default()
+                    },
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(cursor.x - DRAG_GHOST_SIZE / 2.0),
+                        top: Val::Px(cursor.y - DRAG_GHOST_SIZE / 2.0),
+                        width: Val::Px(DRAG_GHOST_SIZE),
+                        height: Val::Px(DRAG_GHOST_SIZE),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::rgba(1.0, 1.0, 1.0, 0.7)),
+                    z_index: ZIndex::Global(10),
+                    ..Default::default()
+                },
+                DragGhost,
+                Name::new("Drag Ghost"),
+            ));
+        }
+        (None, Ok((ghost, _))) => {
+            commands.entity(ghost).despawn_recursive();
+        }
+        (None, Err(_)) => {}
+    }
+}
+
 fn drop_system(
     mut commands: Commands,
-    dropped_query: Query<(Entity, &Draggable), Added<Dropped>>,
+    dropped_query: Query<(Entity, &Draggable, &Dropped), Added<Dropped>>,
+    inventory_box_query: Query<&InventoryBox, With<Hovered>>,
+    grid_box_query: Query<&GridBox, With<Hovered>>,
     windows_query: Query<&Window, With<PrimaryWindow>>,
     camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    mut player_query: Query<&mut Inventory, With<Player>>,
+    mut player_query: Query<(&mut Inventory, &mut CraftingGrid), With<Player>>,
     graphics: Res<Graphics>,
+    item_registry: Res<ItemRegistry>,
+    behaviors: Res<ObjectBehaviors>,
+    ground: Ground,
 ) {
-    if let Some(position) = windows_query.single().cursor_position() {
-        let (camera, camera_transform) = camera_query.single();
-        let mut inventory = player_query.single_mut();
-
-        for (entity, draggable) in dropped_query.iter() {
-            commands.entity(entity).remove::<Dropped>();
-
-            let position = camera
-                .viewport_to_world(camera_transform, position)
-                .map(|ray| ray.origin.truncate())
-                .unwrap();
-
-            WorldObject::Item(draggable.item_type).spawn(
-                &mut commands,
-                &graphics,
-                None,
-                Some(position),
-            );
-            inventory.cost(draggable.item_type, 1);
+    let (camera, camera_transform) = camera_query.single();
+    let (mut inventory, mut grid) = player_query.single_mut();
+
+    for (entity, draggable, Dropped(dragged)) in dropped_query.iter() {
+        commands.entity(entity).remove::<Dropped>();
+
+        // the slot changed under the drag, by a pickup, a finished craft or a quick stack
+        if inventory.get(draggable.slot).map(|stack| stack.item) != Some(dragged.item) {
+            continue;
+        }
+
+        if let Some(target) = inventory_box_query.iter().next() {
+            inventory.move_within(draggable.slot, target.0, dragged.count, &item_registry);
+            continue;
+        }
+        if let Some(grid_box) = grid_box_query.iter().next() {
+            grid.place(grid_box.0, &mut inventory, draggable.slot, &item_registry);
+            continue;
         }
+
+        // dropped outside the hotbar: put one item into the world
+        let item = dragged.item;
+        let definition = item_registry.get(item);
+        if !definition.droppable {
+            continue;
+        }
+        let Some(position) = windows_query.single().cursor_position() else {
+            continue;
+        };
+
+        let mut position = camera
+            .viewport_to_world(camera_transform, position)
+            .map(|ray| ray.origin.truncate())
+            .unwrap();
+        if definition.placeable {
+            position = (position / PLACEMENT_GRID).round() * PLACEMENT_GRID;
+        }
+        // plants need soil; anything else just needs solid ground
+        let fits = match definition.places {
+            Some(world_object) if behaviors.growth(world_object).is_some() => {
+                ground.is_tillable(position)
+            }
+            _ => ground.is_walkable(position),
+        };
+        if !fits {
+            info!("can't drop the {} there", definition.name);
+            continue;
+        }
+        inventory.cost_slot(draggable.slot, 1);
+
+        let world_object = definition.places.unwrap_or(WorldObject::Item(item));
+        world_object.spawn(
+            &mut commands,
+            &graphics,
+            &item_registry,
+            &behaviors,
+            None,
+            Some(position),
+        );
     }
 }
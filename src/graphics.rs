@@ -1,16 +1,34 @@
-use crate::world_object::WorldObject;
-use bevy::{prelude::*, utils::HashMap};
+use crate::{
+    crafting::CraftingBook,
+    item::ItemRegistry,
+    tile::{TileRegistry, TileType},
+    validation::{load_ron, AssetSet},
+    world_object::WorldObject,
+};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashMap},
+};
 use serde::Deserialize;
-use std::fs;
 
 pub struct GraphicsPlugin;
 
 impl Plugin for GraphicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PreStartup, load_graphics);
+        app.add_asset::<GraphicsDescription>()
+            .init_asset_loader::<GraphicsDescriptionLoader>()
+            .add_systems(PreStartup, load_graphics)
+            .add_systems(Update, reload_graphics_system);
     }
 }
 
+/// Sprite rects in `texture.png`, hot reloaded from the asset folder.
+pub const GRAPHICS_DESC_PATH: &str = "texture.graphics.ron";
+/// Size of `texture.png`, which every rect in the descriptions points into.
+pub const TEXTURE_SIZE: Vec2 = Vec2::splat(640.0);
+
 #[derive(Resource)]
 pub struct Graphics {
     pub texture_altas: Handle<TextureAtlas>,
@@ -19,17 +37,41 @@ pub struct Graphics {
     pub npc_texture_altas: Handle<TextureAtlas>,
     pub standard_texture_altas: Handle<TextureAtlas>,
     pub item_index_map: HashMap<WorldObject, (usize, Vec2)>,
+    pub tile_index_map: HashMap<TileType, (usize, Vec2)>,
+    /// Kept so edits to the description reach `reload_graphics_system`.
+    pub graphics_desc: Handle<GraphicsDescription>,
 }
 
-#[derive(Debug, Deserialize)]
-struct GraphicsDescription {
-    map: HashMap<WorldObject, (Rect, Vec2)>,
+#[derive(Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "5b0a6e7c-2f4d-4c1e-9a57-3d8e1f0b6c42"]
+pub struct GraphicsDescription {
+    pub map: HashMap<WorldObject, (Rect, Vec2)>,
+}
+
+/// Loads `*.graphics.ron`; see [`CraftingBookLoader`](crate::crafting::CraftingBookLoader) for why.
+#[derive(Default)]
+pub struct GraphicsDescriptionLoader;
+
+impl AssetLoader for GraphicsDescriptionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let desc: GraphicsDescription = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(desc));
+            Ok(())
+        })
+    }
+    fn extensions(&self) -> &[&str] {
+        &["graphics.ron"]
+    }
 }
 
 impl GraphicsDescription {
     pub fn from_path(path: &str) -> Self {
-        let desc_str = fs::read_to_string(path).unwrap();
-        ron::de::from_str(&desc_str).unwrap()
+        load_ron(path)
     }
 }
 
@@ -37,6 +79,8 @@ pub fn load_graphics(
     mut commands: Commands,
     assets_server: Res<AssetServer>,
     mut texture_assets: ResMut<Assets<TextureAtlas>>,
+    item_registry: Res<ItemRegistry>,
+    tile_registry: Res<TileRegistry>,
 ) {
     let mut player_altas = TextureAtlas::from_grid(
         assets_server.load("player.png"),
@@ -80,18 +124,13 @@ pub fn load_graphics(
     });
     let standard_texture_altas = texture_assets.add(standard_altas);
 
-    let mut texture_altas =
-        TextureAtlas::new_empty(assets_server.load("texture.png"), Vec2::splat(640.0));
-
-    let mut item_index_map = HashMap::default();
-
-    let desc = GraphicsDescription::from_path("assets/graphics_desc.ron");
-
-    for (&world_object, &(rect, size)) in desc.map.iter() {
-        let index = texture_altas.add_texture(rect);
-        item_index_map.insert(world_object, (index, size));
-    }
-
+    let desc = GraphicsDescription::from_path(&format!("assets/{GRAPHICS_DESC_PATH}"));
+    let (texture_altas, item_index_map, tile_index_map) = build_texture_atlas(
+        assets_server.load("texture.png"),
+        &desc,
+        &item_registry,
+        &tile_registry,
+    );
     let atlas_handle = texture_assets.add(texture_altas);
 
     let graphics = Graphics {
@@ -100,7 +139,82 @@ pub fn load_graphics(
         npc_texture_altas,
         standard_texture_altas,
         item_index_map,
+        tile_index_map,
+        graphics_desc: assets_server.load(GRAPHICS_DESC_PATH),
     };
 
     commands.insert_resource(graphics);
 }
+
+/// Cuts the world objects from the description and the items and tiles from their
+/// registries out of `texture`.
+fn build_texture_atlas(
+    texture: Handle<Image>,
+    desc: &GraphicsDescription,
+    item_registry: &ItemRegistry,
+    tile_registry: &TileRegistry,
+) -> (
+    TextureAtlas,
+    HashMap<WorldObject, (usize, Vec2)>,
+    HashMap<TileType, (usize, Vec2)>,
+) {
+    let mut texture_altas = TextureAtlas::new_empty(texture, TEXTURE_SIZE);
+    let mut item_index_map = HashMap::default();
+    let mut tile_index_map = HashMap::default();
+
+    for (&world_object, &(rect, size)) in desc.map.iter() {
+        let index = texture_altas.add_texture(rect);
+        item_index_map.insert(world_object, (index, size));
+    }
+
+    for (&item, definition) in item_registry.items.iter() {
+        let index = texture_altas.add_texture(definition.rect);
+        item_index_map.insert(WorldObject::Item(item), (index, definition.size));
+    }
+
+    for (&tile, definition) in tile_registry.tiles.iter() {
+        let index = texture_altas.add_texture(definition.rect);
+        let size = Vec2::splat(tile_registry.size);
+        tile_index_map.insert(tile, (index, size));
+    }
+
+    (texture_altas, item_index_map, tile_index_map)
+}
+
+/// Rebuilds the atlas in place when the description changes on disk.
+/// A description that doesn't validate is reported and the old graphics stay.
+fn reload_graphics_system(
+    mut desc_events: EventReader<AssetEvent<GraphicsDescription>>,
+    desc_assets: Res<Assets<GraphicsDescription>>,
+    mut texture_assets: ResMut<Assets<TextureAtlas>>,
+    mut graphics: ResMut<Graphics>,
+    crafting_book: Res<CraftingBook>,
+    assets: AssetSet,
+) {
+    for event in desc_events.iter() {
+        let AssetEvent::Modified { handle } = event else {
+            continue;
+        };
+        let Some(desc) = desc_assets
+            .get(handle)
+            .filter(|_| *handle == graphics.graphics_desc)
+        else {
+            continue;
+        };
+        if !assets.accepts(&crafting_book, desc, GRAPHICS_DESC_PATH) {
+            continue;
+        }
+
+        let texture = texture_assets
+            .get(&graphics.texture_altas)
+            .expect("texture atlas not found")
+            .texture
+            .clone();
+        let (texture_altas, item_index_map, tile_index_map) =
+            build_texture_atlas(texture, desc, &assets.item_registry, &assets.tile_registry);
+        texture_assets.set_untracked(&graphics.texture_altas, texture_altas);
+        graphics.item_index_map = item_index_map;
+        graphics.tile_index_map = tile_index_map;
+        info!("reloaded {GRAPHICS_DESC_PATH}");
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{
+    crafting::{
+        CraftBlocker, CraftingBook, CraftingJob, CraftingQueue, CraftingRecipe, Pattern,
+        Surroundings, GRID_SIZE,
+    },
+    discovery::KnownRecipes,
+    drag_and_drop::Hoverable,
+    graphics::Graphics,
+    inventory::{Inventory, ItemStack},
+    item::ItemRegistry,
+    player::Player,
+    ui::{inventory_frame_bundle, inventory_grid_bundle, not_typing, spawn_stack_image},
+    world_object::ItemType,
+};
+use bevy::prelude::*;
+
+pub struct GridCraftingPlugin;
+
+impl Plugin for GridCraftingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_crafting_grid_system.run_if(not_typing),
+                grid_box_system,
+                grid_result_system,
+                cancel_grid_job_system,
+            ),
+        )
+        .add_systems(PostUpdate, update_crafting_grid_panel_system);
+    }
+}
+
+/// Items the player has laid out for a shaped recipe, one per cell, row by row.
+#[derive(Component, Default)]
+pub struct CraftingGrid {
+    pub open: bool,
+    pub cells: [Option<ItemType>; GRID_SIZE * GRID_SIZE],
+}
+
+impl CraftingGrid {
+    pub fn pattern(&self) -> Pattern {
+        Pattern::new(self.cells.chunks(GRID_SIZE).map(<[_]>::to_vec).collect())
+    }
+    pub fn items(&self) -> Vec<(ItemType, usize)> {
+        self.cells.iter().flatten().map(|&item| (item, 1)).collect()
+    }
+    /// Moves one item from the inventory slot into the cell, handing back whatever was there.
+    pub fn place(
+        &mut self,
+        cell: usize,
+        inventory: &mut Inventory,
+        slot: usize,
+        item_registry: &ItemRegistry,
+    ) {
+        let Some(stack) = inventory.get(slot) else {
+            return;
+        };
+        let item = stack.item;
+        if stack.is_unique() {
+            info!(
+                "the {} can't go into the crafting grid",
+                stack.name(item_registry)
+            );
+            return;
+        }
+        if self.cells[cell] == Some(item) {
+            return;
+        }
+
+        inventory.cost_slot(slot, 1);
+        if let Some(previous) = self.cells[cell] {
+            if let Err(err) = inventory.add(previous, 1, item_registry) {
+                info!("can't swap the grid item: {err}");
+                inventory
+                    .add(item, 1, item_registry)
+                    .expect("the item was just taken");
+                return;
+            }
+        }
+        self.cells[cell] = Some(item);
+    }
+}
+
+/// A cell of the crafting grid.
+#[derive(Component)]
+pub struct GridBox(pub usize);
+
+/// Shows what the grid would make; clicking it crafts.
+#[derive(Component)]
+pub struct GridResult;
+
+#[derive(Component)]
+pub struct CraftingGridPanel;
+
+impl CraftingBook {
+    /// The known shaped recipe laid out in the grid, if any.
+    pub fn match_pattern(
+        &self,
+        pattern: &Pattern,
+        known: &KnownRecipes,
+    ) -> Option<&CraftingRecipe> {
+        self.craftable
+            .iter()
+            .find(|recipe| recipe.pattern.as_ref() == Some(pattern) && known.0.contains(&recipe.id))
+    }
+}
+
+/// G opens the grid; closing it puts the laid out items back into the inventory.
+fn toggle_crafting_grid_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut player_query: Query<(&mut Inventory, &mut CraftingGrid), With<Player>>,
+    item_registry: Res<ItemRegistry>,
+) {
+    if !keyboard.just_pressed(KeyCode::G) {
+        return;
+    }
+    let (mut inventory, mut grid) = player_query.single_mut();
+    if grid.open {
+        if let Err(err) = inventory.add_many(&grid.items(), &item_registry) {
+            info!("can't close the crafting grid: {err}");
+            return;
+        }
+        grid.cells = Default::default();
+    }
+    grid.open = !grid.open;
+}
+
+/// Clicking a cell takes its item back.
+fn grid_box_system(
+    box_query: Query<(&Interaction, &GridBox), Changed<Interaction>>,
+    mut player_query: Query<(&mut Inventory, &mut CraftingGrid), With<Player>>,
+    item_registry: Res<ItemRegistry>,
+) {
+    for (interaction, grid_box) in box_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let (mut inventory, mut grid) = player_query.single_mut();
+        let Some(item) = grid.cells[grid_box.0] else {
+            continue;
+        };
+        match inventory.add(item, 1, &item_registry) {
+            Ok(()) => grid.cells[grid_box.0] = None,
+            Err(err) => info!("can't take it from the grid: {err}"),
+        }
+    }
+}
+
+/// Queues the matched recipe, using up the grid.
+fn grid_result_system(
+    result_query: Query<&Interaction, (Changed<Interaction>, With<GridResult>)>,
+    mut player_query: Query<(&mut CraftingGrid, &mut CraftingQueue, &KnownRecipes), With<Player>>,
+    crafting_book: Res<CraftingBook>,
+    surroundings: Res<Surroundings>,
+    item_registry: Res<ItemRegistry>,
+) {
+    if !result_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let (mut grid, mut queue, known) = player_query.single_mut();
+    let Some(recipe) = crafting_book.match_pattern(&grid.pattern(), known) else {
+        return;
+    };
+    let name = &item_registry.get(recipe.main_product()).name;
+    if let Some(station) = recipe.station {
+        if !surroundings.0.contains(&station) {
+            let blocker = CraftBlocker::MissingStation(station);
+            info!(
+                "can't craft the {name}: {}",
+                blocker.describe(&item_registry)
+            );
+            return;
+        }
+    }
+
+    queue.jobs.push_back(CraftingJob {
+        recipe: recipe.id.clone(),
+        timer: Timer::from_seconds(recipe.duration, TimerMode::Once),
+        refund: grid.items(),
+        outputs: recipe.roll_outputs(),
+    });
+    grid.cells = Default::default();
+    info!("queued: {name} × 1");
+}
+
+/// Right-clicking the result cancels the most recently queued shaped recipe, handing
+/// back the items that were laid out for it.
+fn cancel_grid_job_system(
+    mouse_button: Res<Input<MouseButton>>,
+    result_query: Query<&Interaction, With<GridResult>>,
+    crafting_book: Res<CraftingBook>,
+    item_registry: Res<ItemRegistry>,
+    mut player_query: Query<(&mut Inventory, &mut CraftingQueue), With<Player>>,
+) {
+    if !mouse_button.just_pressed(MouseButton::Right)
+        || !result_query
+            .iter()
+            .any(|interaction| *interaction == Interaction::Hovered)
+    {
+        return;
+    }
+
+    let (mut inventory, mut queue) = player_query.single_mut();
+    let Some(job_index) = queue.jobs.iter().rposition(|job| {
+        crafting_book
+            .get(&job.recipe)
+            .is_some_and(|recipe| recipe.pattern.is_some())
+    }) else {
+        return;
+    };
+
+    if let Err(err) = queue.cancel(job_index, &mut inventory, &item_registry) {
+        info!("can't cancel the craft: {err}");
+    }
+}
+
+pub fn update_crafting_grid_panel_system(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    crafting_book: Res<CraftingBook>,
+    surroundings: Res<Surroundings>,
+    item_registry: Res<ItemRegistry>,
+    grid_query: Query<(Ref<CraftingGrid>, Ref<KnownRecipes>), With<Player>>,
+    panel_query: Query<Entity, With<CraftingGridPanel>>,
+) {
+    let (grid, known) = grid_query.single();
+    if !grid.is_changed()
+        && !known.is_changed()
+        && !crafting_book.is_changed()
+        && !surroundings.is_changed()
+        && !graphics.is_changed()
+    {
+        return;
+    }
+
+    for panel in panel_query.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
+    if !grid.open {
+        return;
+    }
+
+    let spawn_box = |commands: &mut Commands, item: Option<ItemType>, count: usize| {
+        let slot = commands
+            .spawn((inventory_frame_bundle(&graphics), Interaction::default()))
+            .id();
+        if let Some(item) = item {
+            let stack = ItemStack {
+                item,
+                count,
+                data: None,
+            };
+            let ent = spawn_stack_image(commands, &graphics, &item_registry, &stack);
+            commands.entity(slot).add_child(ent);
+        }
+        slot
+    };
+
+    let grid_boxes = grid
+        .cells
+        .iter()
+        .enumerate()
+        .map(|(cell, &item)| {
+            let grid_box = spawn_box(&mut commands, item, 1);
+            commands.entity(grid_box).insert((GridBox(cell), Hoverable));
+            grid_box
+        })
+        .collect::<Vec<_>>();
+    let cells = commands
+        .spawn(inventory_grid_bundle(&graphics, GRID_SIZE))
+        .push_children(&grid_boxes)
+        .id();
+
+    let recipe = crafting_book.match_pattern(&grid.pattern(), &known);
+    let preview = recipe.and_then(|recipe| recipe.products.first().copied());
+    let result = spawn_box(
+        &mut commands,
+        preview.map(|(item, _)| item),
+        preview.map_or(0, |(_, amount)| amount),
+    );
+    commands.entity(result).insert(GridResult);
+
+    let hint = recipe
+        .and_then(|recipe| recipe.station)
+        .filter(|station| !surroundings.0.contains(station))
+        .map(|station| CraftBlocker::MissingStation(station).describe(&item_registry))
+        .unwrap_or_default();
+    let arrow = commands
+        .spawn(TextBundle::from_section(
+            "=",
+            TextStyle {
+                font_size: 24.0,
+                ..default()
+            },
+        ))
+        .id();
+    let hint_text = commands
+        .spawn(TextBundle::from_section(
+            hint,
+            TextStyle {
+                font_size: 14.0,
+                color: Color::ORANGE,
+                ..default()
+            },
+        ))
+        .id();
+
+    let row = commands
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .push_children(&[cells, arrow, result])
+        .id();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(16.0),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            },
+            CraftingGridPanel,
+            Name::new("Crafting Grid"),
+        ))
+        .push_children(&[row, hint_text]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crafting::RecipeId, inventory::test_inventory, item::test_registry};
+    use ItemType::{Stone, Wood};
+
+    fn crafting_book() -> CraftingBook {
+        ron::de::from_str(
+            r#"(craftable: [
+                (id: "torch", pattern: [[Wood], [Stone]], products: [(Fire, 1)]),
+                (id: "path", pattern: [[Stone, Stone]], products: [(Stones, 1)]),
+            ])"#,
+        )
+        .unwrap()
+    }
+
+    fn known(ids: &[&str]) -> KnownRecipes {
+        KnownRecipes(ids.iter().map(|id| RecipeId(id.to_string())).collect())
+    }
+
+    fn grid(cells: &[(usize, ItemType)]) -> CraftingGrid {
+        let mut grid = CraftingGrid::default();
+        for &(cell, item) in cells {
+            grid.cells[cell] = Some(item);
+        }
+        grid
+    }
+
+    #[test]
+    fn a_pattern_is_found_wherever_it_sits_in_the_grid() {
+        let crafting_book = crafting_book();
+        let known = known(&["torch", "path"]);
+
+        for (top, bottom) in [(0, 3), (1, 4), (5, 8)] {
+            let grid = grid(&[(top, Wood), (bottom, Stone)]);
+            let recipe = crafting_book.match_pattern(&grid.pattern(), &known);
+            assert_eq!(
+                recipe.map(|recipe| recipe.id.0.as_str()),
+                Some("torch"),
+                "cells {top} and {bottom}"
+            );
+        }
+    }
+
+    #[test]
+    fn jagged_rows_are_padded_with_empty_cells() {
+        let pattern = Pattern::new(vec![
+            vec![None, Some(Wood)],
+            vec![None, Some(Stone), Some(Stone)],
+        ]);
+
+        assert_eq!(
+            pattern,
+            Pattern(vec![vec![Some(Wood), None], vec![Some(Stone), Some(Stone)]])
+        );
+        assert_eq!((pattern.width(), pattern.height()), (2, 2));
+    }
+
+    #[test]
+    fn an_empty_grid_matches_nothing() {
+        let grid = CraftingGrid::default();
+
+        assert_eq!(grid.pattern(), Pattern(Vec::new()));
+        assert!(crafting_book()
+            .match_pattern(&grid.pattern(), &known(&["torch", "path"]))
+            .is_none());
+    }
+
+    #[test]
+    fn an_unknown_recipe_does_not_match() {
+        let grid = grid(&[(0, Stone), (1, Stone)]);
+
+        assert!(crafting_book()
+            .match_pattern(&grid.pattern(), &known(&["torch"]))
+            .is_none());
+    }
+
+    #[test]
+    fn placing_over_an_item_hands_it_back() {
+        let item_registry = test_registry();
+        let mut inventory = test_inventory(&[(Stone, 2), (Wood, 1)], &item_registry);
+        let mut grid = CraftingGrid::default();
+
+        grid.place(4, &mut inventory, 0, &item_registry);
+        grid.place(4, &mut inventory, 1, &item_registry);
+
+        assert_eq!(grid.cells[4], Some(Wood));
+        assert_eq!(inventory.count(Stone), 2);
+        assert_eq!(inventory.count(Wood), 0);
+    }
+}
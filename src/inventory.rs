@@ -1,13 +1,112 @@
-use crate::world_object::ItemType;
-use bevy::{prelude::*, utils::HashMap};
+use crate::{item::ItemRegistry, world_object::ItemType};
+use bevy::prelude::*;
 use bevy_inspector_egui::{prelude::ReflectInspectorOptions, InspectorOptions};
+use std::fmt;
 
-#[derive(Component, Debug, InspectorOptions, Reflect)]
+pub const INVENTORY_NUM: usize = 8;
+
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ItemAdded>()
+            .add_event::<ItemRemoved>()
+            .add_systems(PostUpdate, send_inventory_events_system);
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ItemAdded {
+    pub entity: Entity,
+    pub item: ItemType,
+    pub amount: usize,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ItemRemoved {
+    pub entity: Entity,
+    pub item: ItemType,
+    pub amount: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum InventoryChange {
+    Added(ItemType, usize),
+    Removed(ItemType, usize),
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, InspectorOptions, Reflect)]
+#[reflect(InspectorOptions)]
+pub struct ItemStack {
+    pub item: ItemType,
+    pub count: usize,
+    pub data: Option<ItemData>,
+}
+
+/// Per-instance state. A stack carrying it holds a single item and never merges.
+#[derive(Debug, Default, Clone, PartialEq, Eq, InspectorOptions, Reflect)]
+#[reflect(InspectorOptions)]
+pub struct ItemData {
+    pub durability: Option<u32>,
+    pub quality: Option<u8>,
+    pub custom_name: Option<String>,
+}
+
+impl ItemStack {
+    pub fn is_unique(&self) -> bool {
+        self.data.is_some()
+    }
+    pub fn name<'a>(&'a self, item_registry: &'a ItemRegistry) -> &'a str {
+        self.data
+            .as_ref()
+            .and_then(|data| data.custom_name.as_deref())
+            .unwrap_or(&item_registry.get(self.item).name)
+    }
+    /// Remaining durability in `0.0..=1.0`, if the item wears out at all.
+    pub fn durability_ratio(&self, item_registry: &ItemRegistry) -> Option<f32> {
+        let durability = self.data.as_ref()?.durability?;
+        let max_durability = item_registry.get(self.item).max_durability?;
+        Some(durability as f32 / max_durability as f32)
+    }
+}
+
+#[derive(Component, Debug, Clone, InspectorOptions, Reflect)]
 #[reflect(InspectorOptions)]
 pub struct Inventory {
-    pub items: HashMap<ItemType, usize>,
+    pub slots: Vec<Option<ItemStack>>,
+    /// Drained into `ItemAdded` / `ItemRemoved` events once per frame.
+    #[reflect(ignore)]
+    changes: Vec<InventoryChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryError {
+    NotEnough {
+        item: ItemType,
+        needed: usize,
+        held: usize,
+    },
+    Full {
+        item: ItemType,
+        overflow: usize,
+    },
 }
 
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryError::NotEnough { item, needed, held } => {
+                write!(f, "not enough {item:?}: needed {needed}, held {held}")
+            }
+            InventoryError::Full { item, overflow } => {
+                write!(f, "inventory is full: {overflow} × {item:?} doesn't fit")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
 impl Default for Inventory {
     fn default() -> Self {
         Self::new()
@@ -16,22 +115,571 @@ impl Default for Inventory {
 
 impl Inventory {
     pub fn new() -> Self {
+        Self::with_capacity(INVENTORY_NUM)
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
         Inventory {
-            items: HashMap::default(),
+            slots: vec![None; capacity],
+            changes: Vec::new(),
+        }
+    }
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot).and_then(|stack| stack.as_ref())
+    }
+    pub fn count(&self, item: ItemType) -> usize {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+    pub fn total_weight(&self, item_registry: &ItemRegistry) -> f32 {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|stack| item_registry.get(stack.item).weight * stack.count as f32)
+            .sum()
+    }
+    /// How many more `item` fit into the existing stacks and empty slots.
+    pub fn space_for(&self, item: ItemType, item_registry: &ItemRegistry) -> usize {
+        let max_stack = item_registry.get(item).stack_limit();
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Some(stack) if stack.item == item && !stack.is_unique() => {
+                    max_stack.saturating_sub(stack.count)
+                }
+                Some(_) => 0,
+                None => max_stack,
+            })
+            .sum()
+    }
+    /// Adds `amount` fresh items. Tops up existing stacks of `item` first, then fills
+    /// empty slots in order. Nothing is added unless the whole amount fits.
+    pub fn add(
+        &mut self,
+        item: ItemType,
+        amount: usize,
+        item_registry: &ItemRegistry,
+    ) -> Result<(), InventoryError> {
+        let space = self.space_for(item, item_registry);
+        if space < amount {
+            return Err(InventoryError::Full {
+                item,
+                overflow: amount - space,
+            });
+        }
+
+        let definition = item_registry.get(item);
+        let max_stack = definition.stack_limit();
+        let mut remaining = amount;
+
+        for stack in self.slots.iter_mut().flatten() {
+            if remaining == 0 {
+                break;
+            }
+            if stack.item == item && !stack.is_unique() && stack.count < max_stack {
+                let moved = remaining.min(max_stack - stack.count);
+                stack.count += moved;
+                remaining -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if remaining == 0 {
+                break;
+            }
+            let moved = remaining.min(max_stack);
+            *slot = Some(ItemStack {
+                item,
+                count: moved,
+                data: definition.new_instance_data(),
+            });
+            remaining -= moved;
         }
+
+        self.changes.push(InventoryChange::Added(item, amount));
+        Ok(())
     }
-    pub fn to_vec(&self) -> Vec<(ItemType, usize)> {
-        Vec::from_iter(self.items.clone())
+    /// Adds an existing stack, keeping its per-instance data.
+    pub fn add_stack(
+        &mut self,
+        stack: ItemStack,
+        item_registry: &ItemRegistry,
+    ) -> Result<(), InventoryError> {
+        if !stack.is_unique() {
+            return self.add(stack.item, stack.count, item_registry);
+        }
+
+        let Some(slot) = self.slots.iter_mut().find(|slot| slot.is_none()) else {
+            return Err(InventoryError::Full {
+                item: stack.item,
+                overflow: stack.count,
+            });
+        };
+        self.changes
+            .push(InventoryChange::Added(stack.item, stack.count));
+        *slot = Some(stack);
+        Ok(())
     }
-    pub fn add(&mut self, item: ItemType, amount: usize) {
-        *self.items.entry(item).or_insert(0) += amount;
-        dbg!(&self.items);
+    pub fn empty_slot(&self) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.is_none())
     }
-    pub fn cost(&mut self, item: ItemType, amount: usize) {
-        let count = self.items.entry(item).or_default();
-        *count -= amount;
-        if *count == 0 {
-            self.items.remove_entry(&item);
+    /// Moves up to `amount` items from slot `from` onto slot `to`. They merge into a
+    /// matching stack or fill an empty slot; moving a whole stack onto a different
+    /// item swaps the two.
+    pub fn move_within(
+        &mut self,
+        from: usize,
+        to: usize,
+        amount: usize,
+        item_registry: &ItemRegistry,
+    ) {
+        if from == to || amount == 0 {
+            return;
+        }
+        let (Some(Some(source)), Some(target)) = (self.slots.get(from), self.slots.get(to)) else {
+            return;
+        };
+        let amount = amount.min(source.count);
+
+        match target {
+            None if amount == source.count => {
+                self.slots.swap(from, to);
+            }
+            None => {
+                let mut split = source.clone();
+                split.count = amount;
+                self.slots[to] = Some(split);
+                self.cost_slot_silently(from, amount);
+            }
+            Some(target) if target.item == source.item && !target.is_unique() => {
+                let max_stack = item_registry.get(target.item).stack_limit();
+                let moved = amount.min(max_stack.saturating_sub(target.count));
+                if let Some(target) = self.slots[to].as_mut() {
+                    target.count += moved;
+                }
+                self.cost_slot_silently(from, moved);
+            }
+            Some(_) if amount == source.count => {
+                self.slots.swap(from, to);
+            }
+            Some(_) => {}
         }
     }
+    /// Empties `slot` and hands back whatever was in it.
+    pub fn take(&mut self, slot: usize) -> Option<ItemStack> {
+        let stack = self.slots.get_mut(slot)?.take()?;
+        self.changes
+            .push(InventoryChange::Removed(stack.item, stack.count));
+        Some(stack)
+    }
+    /// Moves the whole stack in `slot` into `other`, or leaves both untouched.
+    pub fn move_stack(
+        &mut self,
+        slot: usize,
+        other: &mut Inventory,
+        item_registry: &ItemRegistry,
+    ) -> Result<(), InventoryError> {
+        let Some(stack) = self.get(slot).cloned() else {
+            return Ok(());
+        };
+        other.add_stack(stack, item_registry)?;
+        self.take(slot);
+        Ok(())
+    }
+    /// Moves every stackable item that `other` already holds, as far as it has room.
+    pub fn quick_stack_into(&mut self, other: &mut Inventory, item_registry: &ItemRegistry) {
+        for slot in 0..self.capacity() {
+            let Some(stack) = self.get(slot).filter(|stack| !stack.is_unique()) else {
+                continue;
+            };
+            let item = stack.item;
+            if other.count(item) == 0 {
+                continue;
+            }
+            let amount = stack.count.min(other.space_for(item, item_registry));
+            if amount > 0 && other.add(item, amount, item_registry).is_ok() {
+                self.cost_slot(slot, amount);
+            }
+        }
+    }
+    /// Takes up to `amount` items from the stack in `slot` alone.
+    pub fn cost_slot(&mut self, slot: usize, amount: usize) {
+        if let Some(stack) = self.slots[slot].as_ref() {
+            self.changes.push(InventoryChange::Removed(
+                stack.item,
+                amount.min(stack.count),
+            ));
+        }
+        self.cost_slot_silently(slot, amount);
+    }
+    /// Like `cost_slot`, for items that stay in this inventory.
+    fn cost_slot_silently(&mut self, slot: usize, amount: usize) {
+        if let Some(stack) = self.slots[slot].as_mut() {
+            stack.count -= amount.min(stack.count);
+            if stack.count == 0 {
+                self.slots[slot] = None;
+            }
+        }
+    }
+    /// Wears down the item in `slot`. Returns `true` if it broke and was removed.
+    pub fn wear(&mut self, slot: usize, amount: u32) -> bool {
+        let Some(stack) = self.slots.get_mut(slot).and_then(|slot| slot.as_mut()) else {
+            return false;
+        };
+        let Some(durability) = stack
+            .data
+            .as_mut()
+            .and_then(|data| data.durability.as_mut())
+        else {
+            return false;
+        };
+
+        *durability = durability.saturating_sub(amount);
+        if *durability > 0 {
+            return false;
+        }
+
+        self.changes
+            .push(InventoryChange::Removed(stack.item, stack.count));
+        self.slots[slot] = None;
+        true
+    }
+    /// Takes from the last matching stack first so the front of the hotbar stays put.
+    /// Nothing is removed unless the whole amount is held.
+    pub fn cost(&mut self, item: ItemType, amount: usize) -> Result<(), InventoryError> {
+        let held = self.count(item);
+        if held < amount {
+            return Err(InventoryError::NotEnough {
+                item,
+                needed: amount,
+                held,
+            });
+        }
+
+        let mut remaining = amount;
+        for slot in self.slots.iter_mut().rev() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(stack) = slot.as_mut().filter(|stack| stack.item == item) {
+                let taken = remaining.min(stack.count);
+                stack.count -= taken;
+                remaining -= taken;
+                if stack.count == 0 {
+                    *slot = None;
+                }
+            }
+        }
+
+        self.changes.push(InventoryChange::Removed(item, amount));
+        Ok(())
+    }
+    /// Adds every `(item, amount)` pair, or nothing at all if they don't all fit.
+    pub fn add_many(
+        &mut self,
+        items: &[(ItemType, usize)],
+        item_registry: &ItemRegistry,
+    ) -> Result<(), InventoryError> {
+        let mut added = self.clone();
+        for &(item, amount) in items {
+            added.add(item, amount, item_registry)?;
+        }
+        *self = added;
+        Ok(())
+    }
+    /// Removes every `(item, amount)` pair, or nothing at all if any of them is short.
+    pub fn try_remove_many(&mut self, items: &[(ItemType, usize)]) -> Result<(), InventoryError> {
+        let mut needed: Vec<(ItemType, usize)> = Vec::with_capacity(items.len());
+        for &(item, amount) in items {
+            match needed
+                .iter_mut()
+                .find(|(needed_item, _)| *needed_item == item)
+            {
+                Some((_, total)) => *total += amount,
+                None => needed.push((item, amount)),
+            }
+        }
+
+        for &(item, amount) in needed.iter() {
+            let held = self.count(item);
+            if held < amount {
+                return Err(InventoryError::NotEnough {
+                    item,
+                    needed: amount,
+                    held,
+                });
+            }
+        }
+
+        for (item, amount) in needed {
+            self.cost(item, amount)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn send_inventory_events_system(
+    mut inventory_query: Query<(Entity, &mut Inventory), Changed<Inventory>>,
+    mut added_events: EventWriter<ItemAdded>,
+    mut removed_events: EventWriter<ItemRemoved>,
+) {
+    for (entity, mut inventory) in inventory_query.iter_mut() {
+        let changes = std::mem::take(&mut inventory.bypass_change_detection().changes);
+        for change in changes {
+            match change {
+                InventoryChange::Added(item, amount) => added_events.send(ItemAdded {
+                    entity,
+                    item,
+                    amount,
+                }),
+                InventoryChange::Removed(item, amount) => removed_events.send(ItemRemoved {
+                    entity,
+                    item,
+                    amount,
+                }),
+            }
+        }
+    }
+}
+
+/// An eight-slot inventory holding `items`.
+#[cfg(test)]
+pub(crate) fn test_inventory(
+    items: &[(ItemType, usize)],
+    item_registry: &ItemRegistry,
+) -> Inventory {
+    let mut inventory = Inventory::new();
+    inventory.add_many(items, item_registry).unwrap();
+    inventory
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::test_registry;
+
+    fn counts(inventory: &Inventory) -> Vec<Option<(ItemType, usize)>> {
+        inventory
+            .slots
+            .iter()
+            .map(|slot| slot.as_ref().map(|stack| (stack.item, stack.count)))
+            .collect()
+    }
+
+    #[test]
+    fn add_tops_up_stacks_before_filling_empty_slots() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(3);
+        inventory.add(ItemType::Stone, 4, &item_registry).unwrap();
+        inventory.add(ItemType::Wood, 1, &item_registry).unwrap();
+        inventory.add(ItemType::Stone, 9, &item_registry).unwrap();
+
+        assert_eq!(
+            counts(&inventory),
+            [
+                Some((ItemType::Stone, 10)),
+                Some((ItemType::Wood, 1)),
+                Some((ItemType::Stone, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_leaves_the_inventory_alone_when_it_overflows() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(2);
+        inventory.add(ItemType::Stone, 8, &item_registry).unwrap();
+        inventory.add(ItemType::Wood, 1, &item_registry).unwrap();
+
+        assert_eq!(
+            inventory.add(ItemType::Stone, 3, &item_registry),
+            Err(InventoryError::Full {
+                item: ItemType::Stone,
+                overflow: 1,
+            })
+        );
+        assert_eq!(inventory.count(ItemType::Stone), 8);
+    }
+
+    #[test]
+    fn cost_takes_from_the_last_stack_first() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(2);
+        inventory.add(ItemType::Stone, 15, &item_registry).unwrap();
+        inventory.cost(ItemType::Stone, 7).unwrap();
+
+        assert_eq!(counts(&inventory), [Some((ItemType::Stone, 8)), None]);
+    }
+
+    #[test]
+    fn cost_leaves_the_inventory_alone_when_short() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(2);
+        inventory.add(ItemType::Stone, 5, &item_registry).unwrap();
+
+        assert_eq!(
+            inventory.cost(ItemType::Stone, 6),
+            Err(InventoryError::NotEnough {
+                item: ItemType::Stone,
+                needed: 6,
+                held: 5,
+            })
+        );
+        assert_eq!(inventory.count(ItemType::Stone), 5);
+    }
+
+    #[test]
+    fn try_remove_many_removes_nothing_if_one_item_is_short() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(2);
+        inventory.add(ItemType::Stone, 5, &item_registry).unwrap();
+        inventory.add(ItemType::Wood, 2, &item_registry).unwrap();
+
+        let result = inventory.try_remove_many(&[(ItemType::Stone, 3), (ItemType::Wood, 3)]);
+        assert_eq!(
+            result,
+            Err(InventoryError::NotEnough {
+                item: ItemType::Wood,
+                needed: 3,
+                held: 2,
+            })
+        );
+        assert_eq!(inventory.count(ItemType::Stone), 5);
+        assert_eq!(inventory.count(ItemType::Wood), 2);
+    }
+
+    #[test]
+    fn try_remove_many_adds_up_repeated_items() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(1);
+        inventory.add(ItemType::Stone, 5, &item_registry).unwrap();
+
+        assert!(inventory
+            .try_remove_many(&[(ItemType::Stone, 3), (ItemType::Stone, 3)])
+            .is_err());
+        assert_eq!(inventory.count(ItemType::Stone), 5);
+
+        inventory
+            .try_remove_many(&[(ItemType::Stone, 2), (ItemType::Stone, 3)])
+            .unwrap();
+        assert_eq!(inventory.count(ItemType::Stone), 0);
+    }
+
+    #[test]
+    fn add_many_adds_nothing_if_one_item_does_not_fit() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(1);
+
+        assert!(inventory
+            .add_many(&[(ItemType::Stone, 5), (ItemType::Wood, 1)], &item_registry)
+            .is_err());
+        assert_eq!(counts(&inventory), [None]);
+    }
+
+    #[test]
+    fn move_within_merges_up_to_the_stack_limit() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(2);
+        inventory.add(ItemType::Stone, 16, &item_registry).unwrap();
+        inventory.move_within(1, 0, 6, &item_registry);
+
+        // the first stack is already full, so nothing moves
+        assert_eq!(
+            counts(&inventory),
+            [Some((ItemType::Stone, 10)), Some((ItemType::Stone, 6))]
+        );
+
+        inventory.move_within(0, 1, 10, &item_registry);
+        assert_eq!(
+            counts(&inventory),
+            [Some((ItemType::Stone, 6)), Some((ItemType::Stone, 10))]
+        );
+    }
+
+    #[test]
+    fn move_within_splits_onto_an_empty_slot() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(2);
+        inventory.add(ItemType::Stone, 7, &item_registry).unwrap();
+        inventory.move_within(0, 1, 4, &item_registry);
+
+        assert_eq!(
+            counts(&inventory),
+            [Some((ItemType::Stone, 3)), Some((ItemType::Stone, 4))]
+        );
+    }
+
+    #[test]
+    fn move_within_swaps_whole_stacks_of_different_items() {
+        let item_registry = test_registry();
+        let mut inventory = Inventory::with_capacity(2);
+        inventory.add(ItemType::Stone, 7, &item_registry).unwrap();
+        inventory.add(ItemType::Wood, 2, &item_registry).unwrap();
+
+        // part of a stack can't go onto a different item
+        inventory.move_within(0, 1, 3, &item_registry);
+        assert_eq!(
+            counts(&inventory),
+            [Some((ItemType::Stone, 7)), Some((ItemType::Wood, 2))]
+        );
+
+        inventory.move_within(0, 1, 7, &item_registry);
+        assert_eq!(
+            counts(&inventory),
+            [Some((ItemType::Wood, 2)), Some((ItemType::Stone, 7))]
+        );
+    }
+
+    #[test]
+    fn move_stack_into_a_full_chest_leaves_both_untouched() {
+        let item_registry = test_registry();
+        let mut player = Inventory::with_capacity(2);
+        player.add(ItemType::Stone, 5, &item_registry).unwrap();
+        let mut chest = Inventory::with_capacity(1);
+        chest.add(ItemType::Stone, 8, &item_registry).unwrap();
+
+        assert_eq!(
+            player.move_stack(0, &mut chest, &item_registry),
+            Err(InventoryError::Full {
+                item: ItemType::Stone,
+                overflow: 3,
+            })
+        );
+        assert_eq!(counts(&player), [Some((ItemType::Stone, 5)), None]);
+        assert_eq!(counts(&chest), [Some((ItemType::Stone, 8))]);
+    }
+
+    #[test]
+    fn quick_stack_only_tops_up_what_the_chest_already_holds() {
+        let item_registry = test_registry();
+        let mut player = Inventory::with_capacity(3);
+        player.add(ItemType::Stone, 5, &item_registry).unwrap();
+        player.add(ItemType::Wood, 4, &item_registry).unwrap();
+        player.add(ItemType::Flower, 3, &item_registry).unwrap();
+        let mut chest = Inventory::with_capacity(2);
+        chest.add(ItemType::Stone, 8, &item_registry).unwrap();
+        chest.add(ItemType::Wood, 1, &item_registry).unwrap();
+
+        player.quick_stack_into(&mut chest, &item_registry);
+
+        assert_eq!(
+            counts(&player),
+            [
+                Some((ItemType::Stone, 3)),
+                None,
+                Some((ItemType::Flower, 3))
+            ]
+        );
+        assert_eq!(
+            counts(&chest),
+            [Some((ItemType::Stone, 10)), Some((ItemType::Wood, 5))]
+        );
+    }
 }
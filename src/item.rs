@@ -0,0 +1,178 @@
+use crate::{
+    inventory::ItemData,
+    validation::load_ron,
+    world_object::{ItemType, WorldObject},
+};
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+pub struct ItemPlugin;
+
+impl Plugin for ItemPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ItemRegistry::from_path(ITEMS_PATH));
+    }
+}
+
+pub const ITEMS_PATH: &str = "assets/items.ron";
+
+#[derive(Resource, Deserialize)]
+pub struct ItemRegistry {
+    pub items: HashMap<ItemType, ItemDefinition>,
+}
+
+impl ItemRegistry {
+    pub fn from_path(path: &str) -> Self {
+        load_ron(path)
+    }
+    pub fn get(&self, item: ItemType) -> &ItemDefinition {
+        self.items
+            .get(&item)
+            .unwrap_or_else(|| panic!("item definition not found: {:?}", item))
+    }
+    /// Items carrying `tag`, in `ItemType` order.
+    pub fn tagged(&self, tag: &str) -> Vec<ItemType> {
+        let mut items = self
+            .items
+            .iter()
+            .filter(|(_, definition)| definition.tags.iter().any(|t| t == tag))
+            .map(|(&item, _)| item)
+            .collect::<Vec<_>>();
+        items.sort();
+        items
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ItemCategory {
+    Material,
+    Tool,
+    Fuel,
+    Building,
+    Food,
+}
+
+impl ItemCategory {
+    /// Order of the crafting book tabs.
+    pub const ALL: [ItemCategory; 5] = [
+        ItemCategory::Tool,
+        ItemCategory::Fuel,
+        ItemCategory::Building,
+        ItemCategory::Food,
+        ItemCategory::Material,
+    ];
+    pub fn label(&self) -> &'static str {
+        match self {
+            ItemCategory::Material => "Materials",
+            ItemCategory::Tool => "Tools",
+            ItemCategory::Fuel => "Fuel",
+            ItemCategory::Building => "Building",
+            ItemCategory::Food => "Food",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ItemDefinition {
+    pub name: String,
+    pub description: String,
+    pub max_stack: usize,
+    /// Items that wear out never stack; every instance tracks its own durability.
+    #[serde(default)]
+    pub max_durability: Option<u32>,
+    pub category: ItemCategory,
+    /// Lets recipes ask for any item of a kind, like "wood" or "fuel".
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Weight of a single item; counts against the player's carry capacity.
+    #[serde(default)]
+    pub weight: f32,
+    /// Can be dragged out of the inventory into the world.
+    pub droppable: bool,
+    /// Snaps to the placement grid when dropped, like a building.
+    pub placeable: bool,
+    /// World object spawned in place of the item when it is dropped.
+    #[serde(default)]
+    pub places: Option<WorldObject>,
+    pub rect: Rect,
+    pub size: Vec2,
+}
+
+impl ItemDefinition {
+    pub fn stack_limit(&self) -> usize {
+        if self.max_durability.is_some() {
+            1
+        } else {
+            self.max_stack
+        }
+    }
+    pub fn new_instance_data(&self) -> Option<ItemData> {
+        self.max_durability.map(|durability| ItemData {
+            durability: Some(durability),
+            ..Default::default()
+        })
+    }
+}
+
+/// Every item, stacking to 10 unless it wears out, with the game's tags.
+#[cfg(test)]
+pub(crate) fn test_registry() -> ItemRegistry {
+    let definition = |name: &str, category, tags: &[&str]| ItemDefinition {
+        name: name.to_string(),
+        description: String::new(),
+        max_stack: 10,
+        max_durability: None,
+        category,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        weight: 1.0,
+        droppable: false,
+        placeable: false,
+        places: None,
+        rect: Rect::new(0.0, 0.0, 16.0, 16.0),
+        size: Vec2::splat(16.0),
+    };
+    let items = [
+        (
+            ItemType::Stone,
+            definition("Stone", ItemCategory::Material, &["stone"]),
+        ),
+        (
+            ItemType::Stones,
+            definition("Stones", ItemCategory::Building, &[]),
+        ),
+        (
+            ItemType::Flower,
+            definition("Flower", ItemCategory::Food, &[]),
+        ),
+        (
+            ItemType::Axe,
+            ItemDefinition {
+                max_durability: Some(20),
+                ..definition("Axe", ItemCategory::Tool, &[])
+            },
+        ),
+        (
+            ItemType::Wood,
+            definition("Wood", ItemCategory::Material, &["wood", "fuel"]),
+        ),
+        (
+            ItemType::Fire,
+            definition("Fire", ItemCategory::Fuel, &["fuel"]),
+        ),
+        (
+            ItemType::Chest,
+            definition("Chest", ItemCategory::Building, &[]),
+        ),
+        (
+            ItemType::Workbench,
+            definition("Workbench", ItemCategory::Building, &[]),
+        ),
+        (
+            ItemType::Scroll,
+            definition("Scroll", ItemCategory::Material, &[]),
+        ),
+    ];
+    ItemRegistry {
+        items: items.into_iter().collect(),
+    }
+}
@@ -1,16 +1,25 @@
-#![allow(clippy::type_complexity)]
-use bevy::prelude::*;
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+use bevy::{asset::ChangeWatcher, prelude::*, utils::Duration};
 
 mod animation;
 mod camera;
+mod chest;
+mod chunk;
 mod crafting;
 mod debug;
+mod discovery;
 mod drag_and_drop;
 mod graphics;
+mod grid_crafting;
 mod inventory;
+mod item;
 mod npc;
+mod planner;
 mod player;
+mod tile;
 mod ui;
+mod validation;
+mod world_gen;
 mod world_object;
 
 fn main() {
@@ -24,16 +33,29 @@ fn main() {
                 ..default()
             }),
             ..default()
+        })
+        .set(AssetPlugin {
+            watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+            ..default()
         });
 
     App::new()
         .add_plugins(default_plugins)
+        .add_plugins(item::ItemPlugin)
         .add_plugins(graphics::GraphicsPlugin)
+        .add_plugins(validation::ValidationPlugin)
         .add_plugins(camera::CameraPlugin)
+        .add_plugins(inventory::InventoryPlugin)
         .add_plugins(player::PlayerPlugin)
         .add_plugins(npc::NpcPlugin)
         .add_plugins(world_object::WorldObjectPlugin)
+        .add_plugins(tile::TilePlugin)
+        .add_plugins(world_gen::WorldGenPlugin)
+        .add_plugins(chunk::ChunkPlugin)
+        .add_plugins(chest::ChestPlugin)
         .add_plugins(crafting::CraftingPlugin)
+        .add_plugins(grid_crafting::GridCraftingPlugin)
+        .add_plugins(discovery::DiscoveryPlugin)
         .add_plugins(animation::AnimationPlugin)
         .add_plugins(ui::UiPlugin)
         .add_plugins(drag_and_drop::DragPlugin)
@@ -13,6 +13,9 @@ impl Plugin for NpcPlugin {
     }
 }
 
+/// Where the NPC stands; the world generator keeps the ground around it dry.
+pub const NPC_POSITION: Vec2 = Vec2::new(200.0, 200.0);
+
 #[derive(Component, Reflect, InspectorOptions)]
 #[reflect(InspectorOptions)]
 pub struct Npc;
@@ -25,7 +28,7 @@ pub fn spawn_npc_system(mut commands: Commands, graphics: Res<Graphics>) {
                 custom_size: Some(Vec2::new(48.0, 64.0)),
                 ..Default::default()
             },
-            transform: Transform::from_xyz(200.0, 200.0, 400.0),
+            transform: Transform::from_translation(NPC_POSITION.extend(400.0)),
             ..Default::default()
         },
         Name::new("Npc"),
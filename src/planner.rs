@@ -0,0 +1,411 @@
+use crate::{
+    crafting::{CraftingBook, CraftingJob, CraftingQueue, CraftingRecipe, Ingredient, RecipeId},
+    inventory::{Inventory, InventoryError},
+    item::ItemRegistry,
+    world_object::ItemType,
+};
+use bevy::{prelude::*, utils::HashMap};
+use std::fmt;
+
+/// A chain of recipes that ends in the target item, worked out against an inventory.
+#[derive(Debug, Default, Clone)]
+pub struct CraftingPlan {
+    /// Recipes in the order they have to be crafted; the target's recipe is last.
+    pub steps: Vec<RecipeId>,
+    /// Raw materials taken from the inventory.
+    pub bill: Vec<(ItemType, usize)>,
+    /// Raw materials the inventory is short of.
+    pub missing: Vec<(Ingredient, usize)>,
+    /// The target plus whatever intermediates are left over.
+    pub outputs: Vec<(ItemType, usize)>,
+}
+
+impl CraftingPlan {
+    pub fn is_reachable(&self) -> bool {
+        !self.steps.is_empty() && self.missing.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanError {
+    /// Every way of making the item needs the item itself, in this order.
+    Cycle(Vec<ItemType>),
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::Cycle(items) => {
+                let chain = items
+                    .iter()
+                    .map(|item| format!("{item:?}"))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                write!(f, "recipe cycle: {chain}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+/// Items on hand while planning, split so the bill only counts what came from the inventory.
+#[derive(Clone, Default)]
+struct PlanState {
+    held: HashMap<ItemType, usize>,
+    crafted: HashMap<ItemType, usize>,
+    bill: HashMap<ItemType, usize>,
+    missing: HashMap<Ingredient, usize>,
+    steps: Vec<RecipeId>,
+}
+
+struct Planner<'a> {
+    crafting_book: &'a CraftingBook,
+    item_registry: &'a ItemRegistry,
+    usable: &'a dyn Fn(&CraftingRecipe) -> bool,
+    state: PlanState,
+    /// Items being crafted further up the chain.
+    visiting: Vec<ItemType>,
+}
+
+impl Planner<'_> {
+    /// Uses up `amount` of the ingredient, crafting whatever isn't on hand.
+    /// Crafted leftovers go first, then the inventory, each in candidate order.
+    fn require(&mut self, ingredient: Ingredient, amount: usize) -> Result<(), PlanError> {
+        let candidates = ingredient.candidates(self.item_registry);
+        let mut remaining = amount;
+        for &item in candidates.iter() {
+            remaining = take(&mut self.state.crafted, item, remaining);
+        }
+        for &item in candidates.iter() {
+            let left = take(&mut self.state.held, item, remaining);
+            if left < remaining {
+                *self.state.bill.entry(item).or_default() += remaining - left;
+            }
+            remaining = left;
+        }
+        if remaining == 0 {
+            return Ok(());
+        }
+
+        let craftable = candidates
+            .into_iter()
+            .find(|&item| !self.recipes_for(item).is_empty());
+        let Some(item) = craftable else {
+            *self.state.missing.entry(ingredient).or_default() += remaining;
+            return Ok(());
+        };
+        self.produce(item, remaining)?;
+        take(&mut self.state.crafted, item, remaining);
+        Ok(())
+    }
+
+    /// Usable recipes making `item`, with how many each craft makes.
+    fn recipes_for(&self, item: ItemType) -> Vec<(usize, usize)> {
+        self.crafting_book
+            .craftable
+            .iter()
+            .enumerate()
+            .filter(|&(_, recipe)| (self.usable)(recipe))
+            .filter_map(|(index, recipe)| {
+                recipe
+                    .products
+                    .iter()
+                    .find(|&&(product, _)| product == item)
+                    .map(|&(_, per_craft)| (index, per_craft))
+            })
+            .filter(|&(_, per_craft)| per_craft > 0)
+            .collect()
+    }
+
+    /// Crafts at least `amount` of `item`, trying each usable recipe in book order.
+    /// The first one that needs nothing missing wins; otherwise the first that didn't cycle.
+    fn produce(&mut self, item: ItemType, amount: usize) -> Result<(), PlanError> {
+        if let Some(start) = self.visiting.iter().position(|&visiting| visiting == item) {
+            let mut cycle = self.visiting[start..].to_vec();
+            cycle.push(item);
+            return Err(PlanError::Cycle(cycle));
+        }
+
+        let candidates = self.recipes_for(item);
+        if candidates.is_empty() {
+            *self
+                .state
+                .missing
+                .entry(Ingredient::Item(item))
+                .or_default() += amount;
+            return Ok(());
+        }
+
+        self.visiting.push(item);
+        let before = self.state.clone();
+        let mut fallback = None;
+        let mut cycle = None;
+        for (index, per_craft) in candidates {
+            match self.craft(index, amount.div_ceil(per_craft)) {
+                Ok(()) if self.state.missing == before.missing => {
+                    self.visiting.pop();
+                    return Ok(());
+                }
+                Ok(()) => {
+                    fallback.get_or_insert(std::mem::replace(&mut self.state, before.clone()));
+                }
+                Err(err) => {
+                    cycle.get_or_insert(err);
+                    self.state = before.clone();
+                }
+            }
+        }
+        self.visiting.pop();
+
+        match (fallback, cycle) {
+            (Some(state), _) => {
+                self.state = state;
+                Ok(())
+            }
+            (None, Some(err)) => Err(err),
+            (None, None) => unreachable!("a candidate either succeeds or fails"),
+        }
+    }
+
+    fn craft(&mut self, recipe_index: usize, times: usize) -> Result<(), PlanError> {
+        let recipe = &self.crafting_book.craftable[recipe_index];
+        for (ingredient, amount) in recipe.requirements() {
+            self.require(ingredient, amount * times)?;
+        }
+
+        self.state
+            .steps
+            .extend(std::iter::repeat_n(recipe.id.clone(), times));
+        for &(product, amount) in recipe.products.iter() {
+            *self.state.crafted.entry(product).or_default() += amount * times;
+        }
+        Ok(())
+    }
+}
+
+/// Takes up to `amount` from `stock` and returns how much is still needed.
+fn take(stock: &mut HashMap<ItemType, usize>, item: ItemType, amount: usize) -> usize {
+    let Some(held) = stock.get_mut(&item) else {
+        return amount;
+    };
+    let taken = amount.min(*held);
+    *held -= taken;
+    amount - taken
+}
+
+fn sorted<T: Ord>(map: HashMap<T, usize>) -> Vec<(T, usize)> {
+    let mut items = map
+        .into_iter()
+        .filter(|&(_, amount)| amount > 0)
+        .collect::<Vec<_>>();
+    items.sort();
+    items
+}
+
+impl CraftingBook {
+    /// Plans crafting `amount` of `target` from the inventory through chains of recipes.
+    /// Items of the target already held are left alone. `usable` filters the recipes,
+    /// e.g. to the known ones with their station nearby.
+    pub fn plan(
+        &self,
+        target: ItemType,
+        amount: usize,
+        inventory: &Inventory,
+        item_registry: &ItemRegistry,
+        usable: &dyn Fn(&CraftingRecipe) -> bool,
+    ) -> Result<CraftingPlan, PlanError> {
+        let mut held = HashMap::default();
+        for stack in inventory.slots.iter().flatten() {
+            if stack.item != target {
+                *held.entry(stack.item).or_default() += stack.count;
+            }
+        }
+
+        let mut planner = Planner {
+            crafting_book: self,
+            item_registry,
+            usable,
+            state: PlanState {
+                held,
+                ..Default::default()
+            },
+            visiting: Vec::new(),
+        };
+        planner.produce(target, amount)?;
+
+        let state = planner.state;
+        Ok(CraftingPlan {
+            steps: state.steps,
+            bill: sorted(state.bill),
+            missing: sorted(state.missing),
+            outputs: sorted(state.crafted),
+        })
+    }
+}
+
+/// Pays the plan's bill and queues the whole chain as one job on the target's recipe.
+pub fn queue_plan(
+    inventory: &mut Inventory,
+    queue: &mut CraftingQueue,
+    crafting_book: &CraftingBook,
+    plan: &CraftingPlan,
+) -> Result<(), InventoryError> {
+    let Some(target_recipe) = plan.steps.last() else {
+        return Ok(());
+    };
+    inventory.try_remove_many(&plan.bill)?;
+
+    let mut outputs = plan.outputs.clone();
+    let mut duration = 0.0;
+    for step in plan.steps.iter() {
+        let recipe = crafting_book.get(step).expect("planned recipe not found");
+        duration += recipe.duration;
+        outputs.extend(recipe.roll_byproducts());
+    }
+
+    queue.jobs.push_back(CraftingJob {
+        recipe: target_recipe.clone(),
+        timer: Timer::from_seconds(duration, TimerMode::Once),
+        refund: plan.bill.clone(),
+        outputs,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{inventory::test_inventory, item::test_registry};
+
+    fn crafting_book(recipes: &str) -> CraftingBook {
+        ron::de::from_str(&format!("(craftable: [{recipes}])")).unwrap()
+    }
+
+    fn ids(steps: &[RecipeId]) -> Vec<&str> {
+        steps.iter().map(|id| id.0.as_str()).collect()
+    }
+
+    #[test]
+    fn plans_the_intermediates_before_the_target() {
+        let item_registry = test_registry();
+        let crafting_book = crafting_book(
+            r#"
+            CraftingRecipe(id: "stones", needed: {Stone: 3}, products: [(Stones, 1)]),
+            CraftingRecipe(id: "workbench", needed: {Stones: 2, Wood: 1}, products: [(Workbench, 1)]),
+            "#,
+        );
+        let inventory =
+            test_inventory(&[(ItemType::Stone, 7), (ItemType::Wood, 1)], &item_registry);
+
+        let plan = crafting_book
+            .plan(ItemType::Workbench, 1, &inventory, &item_registry, &|_| {
+                true
+            })
+            .unwrap();
+        assert!(plan.is_reachable());
+        assert_eq!(ids(&plan.steps), ["stones", "stones", "workbench"]);
+        assert_eq!(plan.bill, [(ItemType::Stone, 6), (ItemType::Wood, 1)]);
+        assert_eq!(plan.outputs, [(ItemType::Workbench, 1)]);
+    }
+
+    #[test]
+    fn reports_what_the_inventory_is_short_of() {
+        let item_registry = test_registry();
+        let crafting_book = crafting_book(
+            r#"
+            CraftingRecipe(id: "stones", needed: {Stone: 3}, products: [(Stones, 1)]),
+            CraftingRecipe(id: "workbench", needed: {Stones: 2, Wood: 1}, products: [(Workbench, 1)]),
+            "#,
+        );
+        let inventory = test_inventory(&[(ItemType::Stone, 4)], &item_registry);
+
+        let plan = crafting_book
+            .plan(ItemType::Workbench, 1, &inventory, &item_registry, &|_| {
+                true
+            })
+            .unwrap();
+        assert!(!plan.is_reachable());
+        assert_eq!(
+            plan.missing,
+            [
+                (Ingredient::Item(ItemType::Stone), 2),
+                (Ingredient::Item(ItemType::Wood), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_a_cycle_with_no_way_out() {
+        let item_registry = test_registry();
+        let crafting_book = crafting_book(
+            r#"
+            CraftingRecipe(id: "stone", needed: {Stones: 1}, products: [(Stone, 3)]),
+            CraftingRecipe(id: "stones", needed: {Stone: 3}, products: [(Stones, 1)]),
+            "#,
+        );
+        let inventory = Inventory::new();
+
+        let err = crafting_book
+            .plan(ItemType::Stone, 1, &inventory, &item_registry, &|_| true)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            PlanError::Cycle(vec![ItemType::Stone, ItemType::Stones, ItemType::Stone])
+        );
+    }
+
+    #[test]
+    fn falls_back_to_a_recipe_that_can_be_paid_for() {
+        let item_registry = test_registry();
+        let crafting_book = crafting_book(
+            r#"
+            CraftingRecipe(id: "fire from stones", needed: {Stones: 1}, products: [(Fire, 1)]),
+            CraftingRecipe(id: "fire", needed: {Wood: 1}, products: [(Fire, 1)]),
+            "#,
+        );
+        let inventory = test_inventory(&[(ItemType::Wood, 1)], &item_registry);
+
+        let plan = crafting_book
+            .plan(ItemType::Fire, 1, &inventory, &item_registry, &|_| true)
+            .unwrap();
+        assert_eq!(ids(&plan.steps), ["fire"]);
+        assert_eq!(plan.bill, [(ItemType::Wood, 1)]);
+    }
+
+    #[test]
+    fn steps_around_a_cycle_when_another_recipe_works() {
+        let item_registry = test_registry();
+        let crafting_book = crafting_book(
+            r#"
+            CraftingRecipe(id: "stone", needed: {Stones: 1}, products: [(Stone, 3)]),
+            CraftingRecipe(id: "stones", needed: {Stone: 3}, products: [(Stones, 1)]),
+            CraftingRecipe(id: "stones from wood", needed: {Wood: 2}, products: [(Stones, 1)]),
+            "#,
+        );
+        let inventory = test_inventory(&[(ItemType::Wood, 2)], &item_registry);
+
+        let plan = crafting_book
+            .plan(ItemType::Stone, 3, &inventory, &item_registry, &|_| true)
+            .unwrap();
+        assert!(plan.is_reachable());
+        assert_eq!(ids(&plan.steps), ["stones from wood", "stone"]);
+    }
+
+    #[test]
+    fn skips_recipes_that_are_not_usable() {
+        let item_registry = test_registry();
+        let crafting_book = crafting_book(
+            r#"
+            CraftingRecipe(id: "fire", needed: {Wood: 1}, products: [(Fire, 1)]),
+            "#,
+        );
+        let inventory = test_inventory(&[(ItemType::Wood, 1)], &item_registry);
+
+        let plan = crafting_book
+            .plan(ItemType::Fire, 1, &inventory, &item_registry, &|_| false)
+            .unwrap();
+        assert!(!plan.is_reachable());
+        assert_eq!(plan.missing, [(Ingredient::Item(ItemType::Fire), 1)]);
+    }
+}
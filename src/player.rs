@@ -1,10 +1,16 @@
 use crate::{
     animation::{FrameTime, SpriteAnimation},
+    crafting::{CraftingBook, CraftingQueue},
+    discovery::KnownRecipes,
     graphics::Graphics,
-    inventory::Inventory,
-    world_object::Pickupable,
+    grid_crafting::CraftingGrid,
+    inventory::{Inventory, INVENTORY_NUM},
+    item::{ItemCategory, ItemRegistry},
+    tile::Ground,
+    ui::not_typing,
+    world_object::{ItemType, Pickupable},
 };
-use bevy::prelude::*;
+use bevy::{input::mouse::MouseWheel, prelude::*};
 use bevy_inspector_egui::{prelude::ReflectInspectorOptions, InspectorOptions};
 
 pub struct PlayerPlugin;
@@ -12,8 +18,16 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_palyer_system)
-            .add_systems(Update, player_movement_system)
-            .add_systems(Update, player_pickup_system);
+            .add_systems(
+                Update,
+                (
+                    player_movement_system,
+                    player_pickup_system,
+                    hotbar_selection_system,
+                )
+                    .run_if(not_typing),
+            )
+            .add_systems(PostUpdate, update_equipped_item_system);
     }
 }
 
@@ -22,6 +36,8 @@ impl Plugin for PlayerPlugin {
 pub struct Player {
     pub speed: f32,
     pub arm_len: f32,
+    /// Total item weight the player can carry before slowing down.
+    pub carry_capacity: f32,
 }
 
 impl Default for Player {
@@ -29,11 +45,48 @@ impl Default for Player {
         Player {
             speed: 180.0,
             arm_len: 50.0,
+            carry_capacity: 30.0,
         }
     }
 }
 
-pub fn spawn_palyer_system(mut commands: Commands, graphics: Res<Graphics>) {
+const MIN_ENCUMBERED_SPEED_FACTOR: f32 = 0.3;
+
+impl Player {
+    /// Scales speed down in proportion to how far `load` is past the carry capacity.
+    pub fn speed_factor(&self, load: f32) -> f32 {
+        if load <= self.carry_capacity {
+            1.0
+        } else {
+            (self.carry_capacity / load).max(MIN_ENCUMBERED_SPEED_FACTOR)
+        }
+    }
+}
+
+/// The hotbar slot the player has selected and the item currently in it.
+#[derive(Component, Default, PartialEq, Reflect, InspectorOptions)]
+#[reflect(InspectorOptions)]
+pub struct EquippedItem {
+    pub slot: usize,
+    pub item: Option<ItemType>,
+}
+
+const HOTBAR_KEYS: [KeyCode; INVENTORY_NUM] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+];
+
+pub fn spawn_palyer_system(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    crafting_book: Res<CraftingBook>,
+) {
     commands.spawn((
         SpriteSheetBundle {
             texture_atlas: graphics.player_texture_altas.clone(),
@@ -46,6 +99,10 @@ pub fn spawn_palyer_system(mut commands: Commands, graphics: Res<Graphics>) {
         },
         Player::default(),
         Inventory::new(),
+        EquippedItem::default(),
+        CraftingQueue::default(),
+        CraftingGrid::default(),
+        KnownRecipes::starting(&crafting_book),
         Name::new("Player"),
         SpriteAnimation {
             start_index: 0,
@@ -58,35 +115,46 @@ pub fn spawn_palyer_system(mut commands: Commands, graphics: Res<Graphics>) {
 
 pub fn player_movement_system(
     keyboard: Res<Input<KeyCode>>,
-    mut player_query: Query<(&mut Transform, &Player, &mut SpriteAnimation)>,
+    mut player_query: Query<(&mut Transform, &Player, &Inventory, &mut SpriteAnimation)>,
+    item_registry: Res<ItemRegistry>,
+    ground: Ground,
     time: Res<Time>,
 ) {
-    let (mut palyer_tf, player, mut animation) = player_query.single_mut();
+    let (mut palyer_tf, player, inventory, mut animation) = player_query.single_mut();
 
-    let delta = player.speed * time.delta_seconds();
+    let load = inventory.total_weight(&item_registry);
+    let delta = player.speed * player.speed_factor(load) * time.delta_seconds();
 
-    if keyboard.any_pressed([KeyCode::A, KeyCode::Left]) {
-        palyer_tf.translation.x -= delta;
+    let step = if keyboard.any_pressed([KeyCode::A, KeyCode::Left]) {
         animation.start_index = 4;
+        Vec2::new(-delta, 0.0)
     } else if keyboard.any_pressed([KeyCode::D, KeyCode::Right]) {
-        palyer_tf.translation.x += delta;
         animation.start_index = 8;
+        Vec2::new(delta, 0.0)
     } else if keyboard.any_pressed([KeyCode::S, KeyCode::Down]) {
-        palyer_tf.translation.y -= delta;
         animation.start_index = 0;
+        Vec2::new(0.0, -delta)
     } else if keyboard.any_pressed([KeyCode::W, KeyCode::Up]) {
-        palyer_tf.translation.y += delta;
         animation.start_index = 12;
+        Vec2::new(0.0, delta)
+    } else {
+        return;
+    };
+
+    let target = palyer_tf.translation.truncate() + step;
+    if ground.is_walkable(target) {
+        palyer_tf.translation += step.extend(0.0);
     }
 }
 
 pub fn player_pickup_system(
     mut commands: Commands,
     keyboard: Res<Input<KeyCode>>,
-    mut player_query: Query<(&Transform, &Player, &mut Inventory), With<Player>>,
+    mut player_query: Query<(&Transform, &Player, &mut Inventory, &EquippedItem), With<Player>>,
     pick_query: Query<(Entity, &Transform, &Pickupable), With<Pickupable>>,
+    item_registry: Res<ItemRegistry>,
 ) {
-    let (player_tf, player, mut inventory) = player_query.single_mut();
+    let (player_tf, player, mut inventory, equipped) = player_query.single_mut();
 
     if keyboard.just_pressed(KeyCode::Space) {
         let closest_item = pick_query
@@ -105,12 +173,72 @@ pub fn player_pickup_system(
             .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
 
         if let Some((ent, pickupable, _)) = closest_item {
+            if let Err(err) = inventory.add(pickupable.item, pickupable.amount, &item_registry) {
+                let name = &item_registry.get(pickupable.item).name;
+                info!("can't pick up the {name}: {err}");
+                return;
+            }
             if let Some(drops) = pickupable.drops {
                 commands.entity(ent).remove::<Pickupable>().insert(drops);
+
+                // harvesting wears down the tool in hand; bare hands and other items don't wear
+                let tool = inventory
+                    .get(equipped.slot)
+                    .map(|stack| stack.item)
+                    .filter(|&item| item_registry.get(item).category == ItemCategory::Tool);
+                if let Some(tool) = tool {
+                    if inventory.wear(equipped.slot, 1) {
+                        info!("the {} broke", item_registry.get(tool).name);
+                    }
+                }
             } else {
                 commands.entity(ent).despawn_recursive();
             }
-            inventory.add(pickupable.item, 1);
+        }
+    }
+}
+
+pub fn hotbar_selection_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut player_query: Query<&mut EquippedItem, With<Player>>,
+) {
+    let mut equipped = player_query.single_mut();
+    let mut slot = equipped.slot;
+
+    if let Some(index) = HOTBAR_KEYS
+        .iter()
+        .position(|&key| keyboard.just_pressed(key))
+    {
+        slot = index;
+    }
+
+    for event in mouse_wheel_events.iter() {
+        if event.y > 0.0 {
+            slot = (slot + INVENTORY_NUM - 1) % INVENTORY_NUM;
+        } else if event.y < 0.0 {
+            slot = (slot + 1) % INVENTORY_NUM;
+        }
+    }
+
+    if equipped.slot != slot {
+        equipped.slot = slot;
+    }
+}
+
+pub fn update_equipped_item_system(
+    mut player_query: Query<
+        (&Inventory, &mut EquippedItem),
+        (
+            With<Player>,
+            Or<(Changed<Inventory>, Changed<EquippedItem>)>,
+        ),
+    >,
+) {
+    for (inventory, mut equipped) in player_query.iter_mut() {
+        let item = inventory.get(equipped.slot).map(|stack| stack.item);
+        if equipped.item != item {
+            equipped.item = item;
         }
     }
 }
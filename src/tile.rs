@@ -0,0 +1,167 @@
+use crate::{
+    chunk::Chunk,
+    graphics::Graphics,
+    validation::load_ron,
+    world_gen::{WorldGenConfig, WorldSeed},
+};
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use bevy_inspector_egui::InspectorOptions;
+use serde::Deserialize;
+
+pub struct TilePlugin;
+
+impl Plugin for TilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TileRegistry::from_path(TILES_PATH))
+            .add_systems(
+                Update,
+                (spawn_ground_tiles_system, update_ground_tiles_system),
+            );
+    }
+}
+
+pub const TILES_PATH: &str = "assets/tiles.ron";
+
+/// Drawn below everything else.
+const GROUND_Z: f32 = -100.0;
+
+#[derive(
+    Deserialize,
+    Component,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    InspectorOptions,
+    Reflect,
+)]
+pub enum TileType {
+    #[default]
+    Grass,
+    Dirt,
+    Water,
+    Path,
+}
+
+#[derive(Resource, Deserialize)]
+pub struct TileRegistry {
+    /// Side of a tile in world units.
+    pub size: f32,
+    pub tiles: HashMap<TileType, TileDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TileDefinition {
+    pub rect: Rect,
+    pub walkable: bool,
+    /// Soil that plants can be put in.
+    pub tillable: bool,
+}
+
+impl TileRegistry {
+    pub fn from_path(path: &str) -> Self {
+        load_ron(path)
+    }
+    pub fn get(&self, tile: TileType) -> &TileDefinition {
+        self.tiles
+            .get(&tile)
+            .unwrap_or_else(|| panic!("tile definition not found: {:?}", tile))
+    }
+    /// The tile that covers `position`.
+    pub fn coord_at(&self, position: Vec2) -> IVec2 {
+        (position / self.size).floor().as_ivec2()
+    }
+}
+
+/// Looks up the ground under a world position.
+#[derive(SystemParam)]
+pub struct Ground<'w> {
+    tiles: Res<'w, TileRegistry>,
+    world_gen: Res<'w, WorldGenConfig>,
+    seed: Res<'w, WorldSeed>,
+}
+
+impl Ground<'_> {
+    pub fn tile_at(&self, position: Vec2) -> TileType {
+        let coord = self.tiles.coord_at(position);
+        self.world_gen.ground_at(self.seed.0, &self.tiles, coord)
+    }
+    pub fn is_walkable(&self, position: Vec2) -> bool {
+        self.tiles.get(self.tile_at(position)).walkable
+    }
+    pub fn is_tillable(&self, position: Vec2) -> bool {
+        self.tiles.get(self.tile_at(position)).tillable
+    }
+}
+
+#[derive(Component)]
+pub struct GroundTile;
+
+/// Lays the ground of every newly loaded chunk under its world objects.
+fn spawn_ground_tiles_system(
+    mut commands: Commands,
+    chunk_query: Query<(Entity, &Chunk), Added<Chunk>>,
+    graphics: Res<Graphics>,
+    ground: Ground,
+) {
+    for (chunk_ent, chunk) in chunk_query.iter() {
+        let area = ground.world_gen.chunk_area(chunk.0);
+        let first = ground.tiles.coord_at(area.min);
+        let last = ground
+            .tiles
+            .coord_at(area.max - Vec2::splat(ground.tiles.size / 2.0));
+
+        let mut tiles = Vec::new();
+        for y in first.y..=last.y {
+            for x in first.x..=last.x {
+                let coord = IVec2::new(x, y);
+                let tile = ground
+                    .world_gen
+                    .ground_at(ground.seed.0, &ground.tiles, coord);
+                let (index, _size) = *graphics
+                    .tile_index_map
+                    .get(&tile)
+                    .unwrap_or_else(|| panic!("tile [{:?}] index not found", tile));
+                let position = (coord.as_vec2() + 0.5) * ground.tiles.size;
+                let ent = commands
+                    .spawn((
+                        SpriteSheetBundle {
+                            texture_atlas: graphics.texture_altas.clone(),
+                            sprite: TextureAtlasSprite {
+                                index,
+                                custom_size: Some(Vec2::splat(ground.tiles.size)),
+                                ..Default::default()
+                            },
+                            transform: Transform::from_translation(position.extend(GROUND_Z)),
+                            ..Default::default()
+                        },
+                        GroundTile,
+                        tile,
+                    ))
+                    .id();
+                tiles.push(ent);
+            }
+        }
+        commands.entity(chunk_ent).push_children(&tiles);
+    }
+}
+
+/// Picks up edits to the atlas.
+fn update_ground_tiles_system(
+    mut tile_query: Query<(&TileType, &mut TextureAtlasSprite), With<GroundTile>>,
+    graphics: Res<Graphics>,
+) {
+    if !graphics.is_changed() {
+        return;
+    }
+    for (tile, mut sprite) in tile_query.iter_mut() {
+        let (index, _size) = *graphics
+            .tile_index_map
+            .get(tile)
+            .unwrap_or_else(|| panic!("tile [{:?}] index not found", tile));
+        sprite.index = index;
+    }
+}
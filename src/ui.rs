@@ -1,44 +1,116 @@
 use crate::{
-    crafting::CraftingBook,
+    crafting::{CraftingBook, CraftingQueue, CraftingRecipe, RecipeId, Surroundings},
+    discovery::KnownRecipes,
     drag_and_drop::{Draggable, Hoverable},
     graphics::Graphics,
-    inventory::Inventory,
+    inventory::{Inventory, ItemStack, INVENTORY_NUM},
+    item::{ItemCategory, ItemRegistry},
+    player::{EquippedItem, Player},
     world_object::WorldObject,
 };
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
 
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Startup,
-            (spawn_inventory_box_system, spawn_crafting_books_system),
-        )
-        .add_systems(
-            PostUpdate,
-            (
-                update_inventory_box_system,
-                update_crafting_book_button_status_system,
-            ),
-        );
+        app.init_resource::<CraftingBookView>()
+            .add_systems(
+                Startup,
+                (spawn_inventory_box_system, spawn_load_text_system),
+            )
+            .add_systems(Update, crafting_book_navigation_system)
+            .add_systems(
+                PostUpdate,
+                (
+                    update_inventory_box_system,
+                    update_hotbar_selection_system,
+                    update_load_text_system,
+                    (
+                        spawn_crafting_books_system,
+                        apply_deferred,
+                        update_crafting_book_button_status_system,
+                    )
+                        .chain(),
+                    update_crafting_progress_system,
+                    update_crafting_tooltip_system,
+                ),
+            );
     }
 }
 
 #[derive(Component)]
 pub struct InventoryBox(pub usize);
 
+/// Root of the crafting book, rebuilt whenever the player learns a recipe or the view changes.
 #[derive(Component)]
-pub struct CraftingButton(pub usize);
+pub struct CraftingBookPanel;
 
-const INVENTORY_NUM: usize = 8;
+/// What the crafting book currently shows.
+#[derive(Resource, Clone, PartialEq)]
+pub struct CraftingBookView {
+    pub category: ItemCategory,
+    /// Matched against product names across all categories when not empty.
+    pub search: String,
+    /// Keys go into `search` instead of moving the player around.
+    pub typing: bool,
+    pub page: usize,
+}
 
-pub fn spawn_inventory_box_system(mut commands: Commands, graphics: Res<Graphics>) {
-    let (index, size) = *graphics
-        .item_index_map
-        .get(&WorldObject::InventoryBox)
-        .expect("inventory box index not found");
+impl Default for CraftingBookView {
+    fn default() -> Self {
+        CraftingBookView {
+            category: ItemCategory::Tool,
+            search: String::new(),
+            typing: false,
+            page: 0,
+        }
+    }
+}
+
+/// Run condition for systems driven by letter keys.
+pub fn not_typing(view: Res<CraftingBookView>) -> bool {
+    !view.typing
+}
+
+#[derive(Component)]
+pub struct CraftingTab(pub ItemCategory);
+
+#[derive(Component)]
+pub struct CraftingSearchField;
+
+/// Turns the crafting book this many pages.
+#[derive(Component)]
+pub struct CraftingPageButton(pub isize);
 
+#[derive(Component)]
+pub struct CraftingButton(pub RecipeId);
+
+/// Fills up from the bottom of a crafting button while its job is running.
+#[derive(Component)]
+pub struct CraftingProgress(pub RecipeId);
+
+/// Badge with how many times the recipe can be crafted from the held ingredients.
+#[derive(Component)]
+pub struct CraftingCount(pub RecipeId);
+
+/// Explains why the recipe next to it can't be crafted.
+#[derive(Component)]
+pub struct CraftingHint(pub RecipeId);
+
+/// Ingredient list of the hovered recipe.
+#[derive(Component)]
+pub struct CraftingTooltip(pub RecipeId);
+
+#[derive(Component)]
+pub struct LoadText;
+
+const SELECTED_SLOT_TINT: Color = Color::rgb(1.0, 0.8, 0.3);
+const RECIPES_PER_PAGE: usize = 5;
+const TOOLTIP_ICON_SIZE: f32 = 20.0;
+const TOOLTIP_OFFSET: f32 = 12.0;
+
+pub fn spawn_inventory_box_system(mut commands: Commands, graphics: Res<Graphics>) {
     let node_bundle = (
         NodeBundle {
             style: Style {
@@ -59,23 +131,10 @@ pub fn spawn_inventory_box_system(mut commands: Commands, graphics: Res<Graphics
     let inventory_boxes = (0..INVENTORY_NUM)
         .map(|i| {
             (
-                AtlasImageBundle {
-                    texture_atlas: graphics.texture_altas.clone(),
-                    texture_atlas_image: UiTextureAtlasImage {
-                        index,
-                        ..Default::default()
-                    },
-                    style: Style {
-                        display: Display::Flex,
-                        align_items: AlignItems::Center,
-                        justify_content: JustifyContent::Center,
-                        width: Val::Px(size.x),
-                        height: Val::Px(size.y),
-                        ..default()
-                    },
-                    ..Default::default()
-                },
+                inventory_frame_bundle(&graphics),
                 InventoryBox(i),
+                Interaction::default(),
+                Hoverable,
             )
         })
         .collect::<Vec<_>>();
@@ -87,38 +146,210 @@ pub fn spawn_inventory_box_system(mut commands: Commands, graphics: Res<Graphics
     });
 }
 
+/// Lays out the known shapeless recipes of the selected tab, one page at a time.
+/// A search term looks through every tab instead.
 pub fn spawn_crafting_books_system(
     mut commands: Commands,
     graphics: Res<Graphics>,
     crafting_book: Res<CraftingBook>,
+    item_registry: Res<ItemRegistry>,
+    mut view: ResMut<CraftingBookView>,
+    known_query: Query<Ref<KnownRecipes>, With<Player>>,
+    panel_query: Query<Entity, With<CraftingBookPanel>>,
 ) {
-    let (index, size) = *graphics
-        .item_index_map
-        .get(&WorldObject::InventoryBox)
-        .expect("inventory box index not found");
+    let known = known_query.single();
+    if !known.is_changed()
+        && !crafting_book.is_changed()
+        && !graphics.is_changed()
+        && !view.is_changed()
+    {
+        return;
+    }
+    for panel in panel_query.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
 
-    let node_bundle = (
-        NodeBundle {
+    let search = view.search.to_lowercase();
+    let shown = crafting_book
+        .craftable
+        .iter()
+        .filter(|recipe| known.0.contains(&recipe.id) && recipe.pattern.is_none())
+        .filter(|recipe| {
+            let definition = item_registry.get(recipe.main_product());
+            if search.is_empty() {
+                definition.category == view.category
+            } else {
+                definition.name.to_lowercase().contains(&search)
+            }
+        })
+        .collect::<Vec<_>>();
+    let page_count = shown.len().div_ceil(RECIPES_PER_PAGE).max(1);
+    let page = view.page.min(page_count - 1);
+    if page != view.page {
+        view.bypass_change_detection().page = page;
+    }
+
+    let tabs = ItemCategory::ALL
+        .iter()
+        .map(|&category| {
+            let selected = search.is_empty() && category == view.category;
+            spawn_text_button(
+                &mut commands,
+                category.label(),
+                selected,
+                CraftingTab(category),
+            )
+        })
+        .collect::<Vec<_>>();
+    let tab_row = commands
+        .spawn(NodeBundle {
+            style: Style {
+                column_gap: Val::Px(2.0),
+                ..default()
+            },
+            ..default()
+        })
+        .push_children(&tabs)
+        .id();
+
+    let search_label = match (view.search.is_empty(), view.typing) {
+        (true, false) => "search...".to_string(),
+        (_, true) => format!("{}_", view.search),
+        (false, false) => view.search.clone(),
+    };
+    let search_field = spawn_text_button(
+        &mut commands,
+        &search_label,
+        view.typing,
+        CraftingSearchField,
+    );
+
+    let recipes = shown
+        .iter()
+        .skip(page * RECIPES_PER_PAGE)
+        .take(RECIPES_PER_PAGE)
+        .map(|recipe| spawn_recipe_entry(&mut commands, &graphics, recipe))
+        .collect::<Vec<_>>();
+    let recipe_column = commands
+        .spawn(NodeBundle {
             style: Style {
-                width: Val::Percent(100.),
-                height: Val::Percent(100.),
                 flex_direction: FlexDirection::Column,
                 align_items: AlignItems::FlexEnd,
-                justify_content: JustifyContent::Center,
                 row_gap: Val::Px(-2.0),
-                ..Default::default()
+                ..default()
             },
             ..default()
-        },
-        Name::new("Crafting Book"),
-    );
+        })
+        .push_children(&recipes)
+        .id();
 
-    let crafting_books = crafting_book
-        .craftable
-        .iter()
-        .enumerate()
-        .map(|(recipe_index, recipe)| {
-            commands
+    let previous = spawn_text_button(&mut commands, "<", false, CraftingPageButton(-1));
+    let page_text = commands
+        .spawn(TextBundle::from_section(
+            format!("{}/{page_count}", page + 1),
+            TextStyle {
+                font_size: 14.0,
+                ..default()
+            },
+        ))
+        .id();
+    let next = spawn_text_button(&mut commands, ">", false, CraftingPageButton(1));
+    let pager = commands
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        })
+        .push_children(&[previous, page_text, next])
+        .id();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(4.0),
+                    ..Default::default()
+                },
+                ..default()
+            },
+            CraftingBookPanel,
+            Name::new("Crafting Book"),
+        ))
+        .push_children(&[tab_row, search_field, recipe_column, pager]);
+}
+
+/// A small labelled button, darker while `selected`.
+fn spawn_text_button(
+    commands: &mut Commands,
+    label: &str,
+    selected: bool,
+    marker: impl Component,
+) -> Entity {
+    let alpha = if selected { 220 } else { 120 };
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::rgba_u8(0, 0, 0, alpha)),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 14.0,
+                    ..default()
+                },
+            ));
+        })
+        .id()
+}
+
+/// The product's icon in an inventory frame, with its button, progress, count and hint.
+fn spawn_recipe_entry(
+    commands: &mut Commands,
+    graphics: &Graphics,
+    recipe: &CraftingRecipe,
+) -> Entity {
+    let (frame_index, size) = inventory_frame(graphics);
+    let (index, _size) = *graphics
+        .item_index_map
+        .get(&WorldObject::Item(recipe.main_product()))
+        .unwrap_or_else(|| panic!("graphics [{:?}] index not found", recipe.main_product()));
+    let id = &recipe.id;
+
+    commands
+        .spawn(AtlasImageBundle {
+            texture_atlas: graphics.texture_altas.clone(),
+            texture_atlas_image: UiTextureAtlasImage {
+                index: frame_index,
+                ..Default::default()
+            },
+            style: Style {
+                display: Display::Flex,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                width: Val::Px(size.x),
+                height: Val::Px(size.y),
+                ..default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
                 .spawn(AtlasImageBundle {
                     texture_atlas: graphics.texture_altas.clone(),
                     texture_atlas_image: UiTextureAtlasImage {
@@ -126,149 +357,545 @@ pub fn spawn_crafting_books_system(
                         ..Default::default()
                     },
                     style: Style {
-                        display: Display::Flex,
-                        align_items: AlignItems::Center,
-                        justify_content: JustifyContent::Center,
-                        width: Val::Px(size.x),
-                        height: Val::Px(size.y),
+                        width: Val::Percent(90.0),
+                        height: Val::Percent(90.0),
                         ..default()
                     },
                     ..Default::default()
                 })
                 .with_children(|parent| {
-                    let (index, _size) = *graphics
-                        .item_index_map
-                        .get(&WorldObject::Item(recipe.preducts))
-                        .unwrap_or_else(|| {
-                            panic!("graphics [{:?}] index not found", &recipe.preducts)
-                        });
-
                     parent
-                        .spawn(AtlasImageBundle {
-                            texture_atlas: graphics.texture_altas.clone(),
-                            texture_atlas_image: UiTextureAtlasImage {
-                                index,
-                                ..Default::default()
-                            },
-                            style: Style {
-                                width: Val::Percent(90.0),
-                                height: Val::Percent(90.0),
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Percent(100.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
                                 ..default()
                             },
-                            ..Default::default()
-                        })
+                            CraftingButton(id.clone()),
+                        ))
                         .with_children(|parent| {
                             parent.spawn((
-                                ButtonBundle {
+                                NodeBundle {
                                     style: Style {
+                                        position_type: PositionType::Absolute,
+                                        left: Val::Px(0.0),
+                                        bottom: Val::Px(0.0),
                                         width: Val::Percent(100.0),
-                                        height: Val::Percent(100.0),
-                                        justify_content: JustifyContent::Center,
-                                        align_items: AlignItems::Center,
+                                        height: Val::Percent(0.0),
                                         ..default()
                                     },
+                                    background_color: BackgroundColor(Color::rgba(
+                                        1.0, 1.0, 1.0, 0.4,
+                                    )),
                                     ..default()
                                 },
-                                CraftingButton(recipe_index),
+                                CraftingProgress(id.clone()),
+                            ));
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    "",
+                                    TextStyle {
+                                        font_size: 14.0,
+                                        ..default()
+                                    },
+                                )
+                                .with_style(Style {
+                                    position_type: PositionType::Absolute,
+                                    right: Val::Px(2.0),
+                                    bottom: Val::Px(0.0),
+                                    ..default()
+                                }),
+                                CraftingCount(id.clone()),
                             ));
                         });
-                })
-                .id()
+                });
+
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        color: Color::ORANGE,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(size.x + 4.0),
+                    ..default()
+                }),
+                CraftingHint(id.clone()),
+            ));
         })
-        .collect::<Vec<_>>();
+        .id()
+}
+
+/// Tab and page clicks, and typing into the search field once it's clicked.
+/// Enter or a click elsewhere leaves the field; Escape stays with the inspector.
+pub fn crafting_book_navigation_system(
+    mouse_button: Res<Input<MouseButton>>,
+    keyboard: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    tab_query: Query<(&Interaction, &CraftingTab), Changed<Interaction>>,
+    page_query: Query<(&Interaction, &CraftingPageButton), Changed<Interaction>>,
+    search_query: Query<&Interaction, (Changed<Interaction>, With<CraftingSearchField>)>,
+    mut view: ResMut<CraftingBookView>,
+) {
+    let mut next = view.clone();
+
+    for (interaction, tab) in tab_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next.category = tab.0;
+            next.search.clear();
+            next.page = 0;
+        }
+    }
+    for (interaction, page_button) in page_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next.page = next.page.saturating_add_signed(page_button.0);
+        }
+    }
 
-    commands.spawn(node_bundle).push_children(&crafting_books);
+    let search_clicked = search_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if search_clicked {
+        next.typing = true;
+    } else if mouse_button.just_pressed(MouseButton::Left) || keyboard.just_pressed(KeyCode::Return)
+    {
+        next.typing = false;
+    }
+
+    if next.typing {
+        for character in characters.iter() {
+            if !character.char.is_control() {
+                next.search.push(character.char);
+                next.page = 0;
+            }
+        }
+        if keyboard.just_pressed(KeyCode::Back) && next.search.pop().is_some() {
+            next.page = 0;
+        }
+    } else {
+        characters.clear();
+    }
+
+    if next != *view {
+        *view = next;
+    }
 }
 
 pub fn update_inventory_box_system(
     mut commands: Commands,
     graphics: Res<Graphics>,
-    inventory_query: Query<&Inventory, Changed<Inventory>>,
-    inventory_boxes_query: Query<(Entity, &InventoryBox, Option<&Children>), With<InventoryBox>>,
+    item_registry: Res<ItemRegistry>,
+    inventory_query: Query<Ref<Inventory>, With<Player>>,
+    mut inventory_boxes_query: Query<
+        (
+            Entity,
+            &InventoryBox,
+            &mut UiTextureAtlasImage,
+            Option<&Children>,
+        ),
+        With<InventoryBox>,
+    >,
 ) {
-    if let Ok(inventory) = inventory_query.get_single() {
-        let inventory_vec = inventory.to_vec();
-
-        // TODO: fix the inventory box order
-        for (inventory_box_ent, inventory_box, children) in inventory_boxes_query.iter() {
-            if let Some(children) = children {
-                for &child_ent in children {
-                    commands.entity(child_ent).despawn_recursive();
-                }
-            }
+    let inventory = inventory_query.single();
+    if !inventory.is_changed() && !graphics.is_changed() {
+        return;
+    }
+
+    // atlas indices move around when the graphics are reloaded
+    let (frame_index, _size) = inventory_frame(&graphics);
 
-            if inventory_box.0 >= inventory_vec.len() {
-                continue;
+    for (inventory_box_ent, inventory_box, mut frame, children) in inventory_boxes_query.iter_mut()
+    {
+        if frame.index != frame_index {
+            frame.index = frame_index;
+        }
+        if let Some(children) = children {
+            for &child_ent in children {
+                commands.entity(child_ent).despawn_recursive();
             }
+        }
 
-            let (item_type, count) = inventory_vec[inventory_box.0];
+        let Some(stack) = inventory.get(inventory_box.0) else {
+            continue;
+        };
 
-            let (index, _size) = *graphics
-                .item_index_map
-                .get(&WorldObject::Item(item_type))
-                .unwrap_or_else(|| panic!("inventory box [{:?}] index not found", item_type));
+        let ent = spawn_stack_image(&mut commands, &graphics, &item_registry, stack);
+        commands.entity(ent).insert((
+            Hoverable,
+            Draggable {
+                slot: inventory_box.0,
+            },
+        ));
 
-            let aib = AtlasImageBundle {
-                texture_atlas: graphics.texture_altas.clone(),
-                texture_atlas_image: UiTextureAtlasImage {
-                    index,
-                    ..Default::default()
-                },
-                style: Style {
-                    display: Display::Flex,
-                    align_items: AlignItems::FlexEnd,
-                    justify_content: JustifyContent::FlexEnd,
-                    width: Val::Percent(90.0),
-                    height: Val::Percent(90.0),
-                    ..default()
-                },
-                ..Default::default()
-            };
-            let mut ent = commands.spawn(aib);
+        commands.entity(inventory_box_ent).add_child(ent);
+    }
+}
 
-            if item_type.is_draggable() {
-                ent.insert((Hoverable, Draggable { item_type }));
-            }
+/// Atlas index and size of the frame inventory slots are drawn in.
+pub fn inventory_frame(graphics: &Graphics) -> (usize, Vec2) {
+    *graphics
+        .item_index_map
+        .get(&WorldObject::InventoryBox)
+        .expect("inventory box index not found")
+}
 
-            let ent = ent
-                .with_children(|parent| {
-                    parent.spawn(TextBundle {
-                        text: Text {
-                            alignment: TextAlignment::Right,
-                            sections: vec![TextSection {
-                                value: count.to_string(),
-                                ..Default::default()
-                            }],
-                            ..Default::default()
-                        },
-                        style: Style {
-                            margin: UiRect::px(0.0, 3.0, 0.0, 3.0),
+/// The empty frame of an inventory slot, centring whatever goes into it.
+pub fn inventory_frame_bundle(graphics: &Graphics) -> AtlasImageBundle {
+    let (index, size) = inventory_frame(graphics);
+    AtlasImageBundle {
+        texture_atlas: graphics.texture_altas.clone(),
+        texture_atlas_image: UiTextureAtlasImage {
+            index,
+            ..Default::default()
+        },
+        style: Style {
+            display: Display::Flex,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            width: Val::Px(size.x),
+            height: Val::Px(size.y),
+            ..default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Wraps inventory frames into rows of `columns`.
+pub fn inventory_grid_bundle(graphics: &Graphics, columns: usize) -> NodeBundle {
+    let (_index, size) = inventory_frame(graphics);
+    NodeBundle {
+        style: Style {
+            width: Val::Px(size.x * columns as f32),
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Wrap,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+/// Spawns the icon of `stack` with its count, or a durability bar for items that wear out.
+pub fn spawn_stack_image(
+    commands: &mut Commands,
+    graphics: &Graphics,
+    item_registry: &ItemRegistry,
+    stack: &ItemStack,
+) -> Entity {
+    let (index, _size) = *graphics
+        .item_index_map
+        .get(&WorldObject::Item(stack.item))
+        .unwrap_or_else(|| panic!("inventory box [{:?}] index not found", stack.item));
+
+    let aib = AtlasImageBundle {
+        texture_atlas: graphics.texture_altas.clone(),
+        texture_atlas_image: UiTextureAtlasImage {
+            index,
+            ..Default::default()
+        },
+        style: Style {
+            display: Display::Flex,
+            align_items: AlignItems::FlexEnd,
+            justify_content: JustifyContent::FlexEnd,
+            width: Val::Percent(90.0),
+            height: Val::Percent(90.0),
+            ..default()
+        },
+        ..Default::default()
+    };
+    let definition = item_registry.get(stack.item);
+    let durability_ratio = stack.durability_ratio(item_registry);
+    let count = stack.count;
+
+    commands
+        .spawn((
+            aib,
+            Name::new(format!(
+                "{}: {}",
+                stack.name(item_registry),
+                definition.description
+            )),
+        ))
+        .with_children(|parent| match durability_ratio {
+            Some(ratio) => {
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(10.0),
+                        bottom: Val::Px(2.0),
+                        width: Val::Percent(80.0 * ratio),
+                        height: Val::Px(3.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::rgb(1.0 - ratio, ratio, 0.0)),
+                    ..default()
+                });
+            }
+            None => {
+                parent.spawn(TextBundle {
+                    text: Text {
+                        alignment: TextAlignment::Right,
+                        sections: vec![TextSection {
+                            value: count.to_string(),
                             ..Default::default()
-                        },
+                        }],
                         ..Default::default()
-                    });
-                })
-                .id();
+                    },
+                    style: Style {
+                        margin: UiRect::px(0.0, 3.0, 0.0, 3.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            }
+        })
+        .id()
+}
 
-            commands.entity(inventory_box_ent).add_child(ent);
+pub fn spawn_load_text_system(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(8.0),
+            bottom: Val::Px(8.0),
+            ..default()
+        }),
+        LoadText,
+        Name::new("Load Text"),
+    ));
+}
+
+pub fn update_load_text_system(
+    player_query: Query<(&Player, &Inventory), Changed<Inventory>>,
+    mut load_text_query: Query<&mut Text, With<LoadText>>,
+    item_registry: Res<ItemRegistry>,
+) {
+    if let Ok((player, inventory)) = player_query.get_single() {
+        let load = inventory.total_weight(&item_registry);
+        let mut text = load_text_query.single_mut();
+        text.sections[0].value = format!("Load {load:.1} / {:.1}", player.carry_capacity);
+        text.sections[0].style.color = if load > player.carry_capacity {
+            Color::ORANGE_RED
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+pub fn update_hotbar_selection_system(
+    equipped_query: Query<&EquippedItem, Changed<EquippedItem>>,
+    mut inventory_boxes_query: Query<(&InventoryBox, &mut BackgroundColor)>,
+) {
+    if let Ok(equipped) = equipped_query.get_single() {
+        for (inventory_box, mut bgc) in inventory_boxes_query.iter_mut() {
+            *bgc = if inventory_box.0 == equipped.slot {
+                BackgroundColor(SELECTED_SLOT_TINT)
+            } else {
+                BackgroundColor::default()
+            };
         }
     }
 }
 
 pub fn update_crafting_book_button_status_system(
-    inventory_query: Query<&Inventory, Changed<Inventory>>,
+    inventory_query: Query<Ref<Inventory>, With<Player>>,
+    surroundings: Res<Surroundings>,
+    new_button_query: Query<(), Added<CraftingButton>>,
     mut crafting_button_query: Query<(&CraftingButton, &mut BackgroundColor), With<CraftingButton>>,
+    mut crafting_hint_query: Query<(&CraftingHint, &mut Text), Without<CraftingCount>>,
+    mut crafting_count_query: Query<(&CraftingCount, &mut Text), Without<CraftingHint>>,
     crafting_book: Res<CraftingBook>,
+    item_registry: Res<ItemRegistry>,
 ) {
+    let inventory = inventory_query.single();
+    if !inventory.is_changed() && !surroundings.is_changed() && new_button_query.is_empty() {
+        return;
+    }
+
     for (crafting_btn, mut bgc) in crafting_button_query.iter_mut() {
-        let crafting_index = crafting_btn.0;
-        let crafting_recipe = &crafting_book.craftable[crafting_index];
-        if let Ok(inventory) = inventory_query.get_single() {
-            if crafting_recipe.can_craft(inventory) {
-                *bgc = BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.0));
-            } else {
-                *bgc = BackgroundColor(Color::rgba_u8(0, 0, 0, 200));
-            }
+        let Some(crafting_recipe) = crafting_book.get(&crafting_btn.0) else {
+            continue;
+        };
+        if crafting_recipe.can_craft(&inventory, &surroundings, &item_registry) {
+            *bgc = BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.0));
+        } else {
+            *bgc = BackgroundColor(Color::rgba_u8(0, 0, 0, 200));
+        }
+    }
+
+    for (crafting_hint, mut text) in crafting_hint_query.iter_mut() {
+        let Some(crafting_recipe) = crafting_book.get(&crafting_hint.0) else {
+            continue;
+        };
+        text.sections[0].value =
+            match crafting_recipe.check(&inventory, &surroundings, &item_registry) {
+                Ok(()) => String::new(),
+                Err(blocker) => blocker.describe(&item_registry),
+            };
+    }
+
+    for (crafting_count, mut text) in crafting_count_query.iter_mut() {
+        let Some(crafting_recipe) = crafting_book.get(&crafting_count.0) else {
+            continue;
+        };
+        let max = crafting_recipe.max_craftable(&inventory, &item_registry);
+        text.sections[0].value = if max > 0 {
+            max.to_string()
+        } else {
+            String::new()
+        };
+    }
+}
+
+pub fn update_crafting_progress_system(
+    queue_query: Query<&CraftingQueue, With<Player>>,
+    mut progress_query: Query<(&CraftingProgress, &mut Style)>,
+) {
+    let front_job = queue_query.single().jobs.front();
+    for (progress, mut style) in progress_query.iter_mut() {
+        let percent = front_job
+            .filter(|job| job.recipe == progress.0)
+            .map_or(0.0, |job| job.timer.percent() * 100.0);
+        if style.height != Val::Percent(percent) {
+            style.height = Val::Percent(percent);
+        }
+    }
+}
+
+/// Shows the hovered recipe's ingredients next to the cursor, green when enough are held.
+pub fn update_crafting_tooltip_system(
+    mut commands: Commands,
+    windows_query: Query<&Window, With<PrimaryWindow>>,
+    button_query: Query<(&Interaction, &CraftingButton)>,
+    inventory_query: Query<Ref<Inventory>, With<Player>>,
+    mut tooltip_query: Query<(Entity, &CraftingTooltip, &mut Style)>,
+    graphics: Res<Graphics>,
+    crafting_book: Res<CraftingBook>,
+    item_registry: Res<ItemRegistry>,
+) {
+    let window = windows_query.single();
+    let inventory = inventory_query.single();
+    let hovered = button_query
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .and_then(|(_, crafting_button)| crafting_book.get(&crafting_button.0));
+    let (Some(recipe), Some(cursor)) = (hovered, window.cursor_position()) else {
+        for (tooltip, _, _) in tooltip_query.iter() {
+            commands.entity(tooltip).despawn_recursive();
+        }
+        return;
+    };
+
+    // the crafting book sits on the right edge, so the tooltip opens to the left of the cursor
+    let right = Val::Px(window.width() - cursor.x + TOOLTIP_OFFSET);
+    let top = Val::Px(cursor.y + TOOLTIP_OFFSET);
+
+    let mut up_to_date = false;
+    for (tooltip, crafting_tooltip, mut style) in tooltip_query.iter_mut() {
+        if crafting_tooltip.0 == recipe.id && !inventory.is_changed() && !graphics.is_changed() {
+            style.right = right;
+            style.top = top;
+            up_to_date = true;
+        } else {
+            commands.entity(tooltip).despawn_recursive();
         }
     }
+    if up_to_date {
+        return;
+    }
+
+    let rows = recipe
+        .requirements()
+        .into_iter()
+        .map(|(ingredient, needed)| {
+            // a tag shows the first of its items the player holds
+            let candidates = ingredient.candidates(&item_registry);
+            let icon = candidates
+                .iter()
+                .find(|&&item| inventory.count(item) > 0)
+                .or(candidates.first())
+                .copied()
+                .unwrap_or_else(|| panic!("tooltip [{:?}] has no items", ingredient));
+            let (index, _size) = *graphics
+                .item_index_map
+                .get(&WorldObject::Item(icon))
+                .unwrap_or_else(|| panic!("tooltip [{:?}] index not found", icon));
+            let held: usize = candidates.iter().map(|&item| inventory.count(item)).sum();
+            let color = if held >= needed {
+                Color::GREEN
+            } else {
+                Color::ORANGE_RED
+            };
+
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(AtlasImageBundle {
+                        texture_atlas: graphics.texture_altas.clone(),
+                        texture_atlas_image: UiTextureAtlasImage {
+                            index,
+                            ..Default::default()
+                        },
+                        style: Style {
+                            width: Val::Px(TOOLTIP_ICON_SIZE),
+                            height: Val::Px(TOOLTIP_ICON_SIZE),
+                            ..default()
+                        },
+                        ..Default::default()
+                    });
+                    parent.spawn(TextBundle::from_section(
+                        format!("{held}/{needed} {}", ingredient.describe(&item_registry)),
+                        TextStyle {
+                            font_size: 14.0,
+                            color,
+                            ..default()
+                        },
+                    ));
+                })
+                .id()
+        })
+        .collect::<Vec<_>>();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right,
+                    top,
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(4.0)),
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::rgba_u8(0, 0, 0, 200)),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            CraftingTooltip(recipe.id.clone()),
+            Name::new("Crafting Tooltip"),
+        ))
+        .push_children(&rows);
 }
@@ -0,0 +1,656 @@
+use crate::{
+    crafting::{CraftingBook, CraftingRecipe, CRAFTING_BOOK_PATH, GRID_SIZE},
+    graphics::{load_graphics, GraphicsDescription, GRAPHICS_DESC_PATH, TEXTURE_SIZE},
+    item::{ItemRegistry, ITEMS_PATH},
+    tile::{TileRegistry, TileType, TILES_PATH},
+    world_gen::{WorldGenConfig, WORLD_GEN_PATH},
+    world_object::{ItemType, ObjectBehaviors, WorldObject, WORLD_OBJECTS_PATH},
+};
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::{Enum, TypeInfo, Typed, VariantInfo},
+};
+use serde::de::DeserializeOwned;
+use std::{fmt, fs};
+
+pub struct ValidationPlugin;
+
+impl Plugin for ValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, validate_assets_system.before(load_graphics));
+    }
+}
+
+/// Reads a description file, panicking with its path if it is missing or malformed.
+pub fn load_ron<T: DeserializeOwned>(path: &str) -> T {
+    let desc_str = fs::read_to_string(path).unwrap_or_else(|err| panic!("{path}: {err}"));
+    ron::de::from_str(&desc_str).unwrap_or_else(|err| panic!("{path}: {err}"))
+}
+
+/// A problem in one of the description files.
+#[derive(Debug, PartialEq)]
+pub struct AssetError {
+    pub file: &'static str,
+    pub entry: String,
+    pub message: String,
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.file, self.entry, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Report(Vec<AssetError>);
+
+impl Report {
+    fn push(&mut self, file: &'static str, entry: impl Into<String>, message: impl Into<String>) {
+        self.0.push(AssetError {
+            file,
+            entry: entry.into(),
+            message: message.into(),
+        });
+    }
+}
+
+/// The loaded descriptions that an edited crafting book or graphics description is
+/// checked against.
+#[derive(SystemParam)]
+pub struct AssetSet<'w> {
+    pub item_registry: Res<'w, ItemRegistry>,
+    pub behaviors: Res<'w, ObjectBehaviors>,
+    pub world_gen: Res<'w, WorldGenConfig>,
+    pub tile_registry: Res<'w, TileRegistry>,
+}
+
+impl AssetSet<'_> {
+    pub fn validate(
+        &self,
+        crafting_book: &CraftingBook,
+        graphics_desc: &GraphicsDescription,
+    ) -> Vec<AssetError> {
+        validate(
+            crafting_book,
+            &self.item_registry,
+            &self.behaviors,
+            &self.world_gen,
+            &self.tile_registry,
+            graphics_desc,
+        )
+    }
+    /// Logs every problem and returns `false` if the edit to `path` has to be dropped.
+    pub fn accepts(
+        &self,
+        crafting_book: &CraftingBook,
+        graphics_desc: &GraphicsDescription,
+        path: &str,
+    ) -> bool {
+        let errors = self.validate(crafting_book, graphics_desc);
+        for error in errors.iter() {
+            error!("{error}");
+        }
+        if !errors.is_empty() {
+            warn!("{path} not reloaded");
+        }
+        errors.is_empty()
+    }
+}
+
+/// Checks the descriptions against each other and panics with every problem found.
+fn validate_assets_system(crafting_book: Res<CraftingBook>, assets: AssetSet) {
+    let graphics_desc = GraphicsDescription::from_path(&format!("assets/{GRAPHICS_DESC_PATH}"));
+    let errors = assets.validate(&crafting_book, &graphics_desc);
+    if errors.is_empty() {
+        return;
+    }
+
+    for error in errors.iter() {
+        error!("{error}");
+    }
+    let list = errors
+        .iter()
+        .map(|error| format!("  {error}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    panic!("found {} problems in the game data:\n{list}", errors.len());
+}
+
+fn validate(
+    crafting_book: &CraftingBook,
+    item_registry: &ItemRegistry,
+    behaviors: &ObjectBehaviors,
+    world_gen: &WorldGenConfig,
+    tile_registry: &TileRegistry,
+    graphics_desc: &GraphicsDescription,
+) -> Vec<AssetError> {
+    let mut report = Report::default();
+    validate_graphics(&mut report, item_registry, graphics_desc);
+    validate_crafting_book(&mut report, crafting_book, item_registry, graphics_desc);
+    validate_behaviors(&mut report, behaviors, item_registry, graphics_desc);
+    validate_world_gen(&mut report, world_gen, item_registry, graphics_desc);
+    validate_tiles(&mut report, tile_registry, world_gen);
+    report.0
+}
+
+fn validate_graphics(
+    report: &mut Report,
+    item_registry: &ItemRegistry,
+    graphics_desc: &GraphicsDescription,
+) {
+    for (world_object, (rect, _size)) in graphics_desc.map.iter() {
+        if let Some(message) = rect_problem(rect) {
+            report.push(GRAPHICS_DESC_PATH, format!("{world_object:?}"), message);
+        }
+    }
+    for (item, definition) in item_registry.items.iter() {
+        if let Some(message) = rect_problem(&definition.rect) {
+            report.push(ITEMS_PATH, format!("{item:?}"), message);
+        }
+    }
+
+    for name in unit_variants(WorldObject::type_info()) {
+        if !graphics_desc
+            .map
+            .keys()
+            .any(|world_object| world_object.variant_name() == name)
+        {
+            report.push(
+                GRAPHICS_DESC_PATH,
+                name,
+                "world object has no graphics entry",
+            );
+        }
+    }
+    for name in unit_variants(ItemType::type_info()) {
+        if !item_registry
+            .items
+            .keys()
+            .any(|item| item.variant_name() == name)
+        {
+            report.push(ITEMS_PATH, name, "item has no definition");
+        }
+    }
+}
+
+fn validate_crafting_book(
+    report: &mut Report,
+    crafting_book: &CraftingBook,
+    item_registry: &ItemRegistry,
+    graphics_desc: &GraphicsDescription,
+) {
+    for (index, recipe) in crafting_book.craftable.iter().enumerate() {
+        let entry = format!("recipe #{index} ({:?})", recipe.id.0);
+        let mut problem = |message: String| report.push(CRAFTING_BOOK_PATH, entry.clone(), message);
+
+        if recipe.products.is_empty() {
+            problem("has no products".to_string());
+        }
+        for &(item, amount) in recipe.products.iter() {
+            if !has_graphics(item_registry, graphics_desc, WorldObject::Item(item)) {
+                problem(format!("product {item:?} has no graphics entry"));
+            }
+            if amount == 0 {
+                problem(format!("product {item:?} has a quantity of zero"));
+            }
+        }
+        for (&item, &amount) in recipe.needed.iter() {
+            if !has_graphics(item_registry, graphics_desc, WorldObject::Item(item)) {
+                problem(format!("ingredient {item:?} has no graphics entry"));
+            }
+            if amount == 0 {
+                problem(format!("ingredient {item:?} has a quantity of zero"));
+            }
+        }
+        for (tag, &amount) in recipe.tagged.iter() {
+            if item_registry.tagged(tag).is_empty() {
+                problem(format!("no item carries the tag {tag:?}"));
+            }
+            if amount == 0 {
+                problem(format!("tag {tag:?} has a quantity of zero"));
+            }
+        }
+        for byproduct in recipe.byproducts.iter() {
+            if !has_graphics(
+                item_registry,
+                graphics_desc,
+                WorldObject::Item(byproduct.item),
+            ) {
+                problem(format!(
+                    "byproduct {:?} has no graphics entry",
+                    byproduct.item
+                ));
+            }
+            if !(0.0..=1.0).contains(&byproduct.chance) {
+                problem(format!(
+                    "byproduct {:?} has a chance outside 0.0..=1.0",
+                    byproduct.item
+                ));
+            }
+        }
+        if let Some(station) = recipe.station {
+            if !has_graphics(item_registry, graphics_desc, station) {
+                problem(format!("station {station:?} has no graphics entry"));
+            }
+        }
+        if let Some(pattern) = &recipe.pattern {
+            if pattern.height() == 0 {
+                problem("pattern has no items".to_string());
+            }
+            if pattern.width() > GRID_SIZE || pattern.height() > GRID_SIZE {
+                problem(format!(
+                    "pattern is {}×{}, larger than the {GRID_SIZE}×{GRID_SIZE} grid",
+                    pattern.width(),
+                    pattern.height()
+                ));
+            }
+            if !recipe.tagged.is_empty() {
+                problem("shaped recipes can't take tagged ingredients".to_string());
+            }
+            if let Some(original) = crafting_book.craftable[..index]
+                .iter()
+                .position(|other| other.pattern.as_ref() == Some(pattern))
+            {
+                problem(format!("has the same pattern as recipe #{original}"));
+            }
+        }
+        if let Some(original) = crafting_book.craftable[..index]
+            .iter()
+            .position(|other| same_recipe(recipe, other))
+        {
+            problem(format!("duplicates recipe #{original}"));
+        }
+        if let Some(original) = crafting_book.craftable[..index]
+            .iter()
+            .position(|other| other.id == recipe.id)
+        {
+            problem(format!("reuses the id of recipe #{original}"));
+        }
+    }
+}
+
+fn validate_behaviors(
+    report: &mut Report,
+    behaviors: &ObjectBehaviors,
+    item_registry: &ItemRegistry,
+    graphics_desc: &GraphicsDescription,
+) {
+    for (&world_object, behavior) in behaviors.behaviors.iter() {
+        let entry = format!("{world_object:?}");
+        let mut problem = |message: String| report.push(WORLD_OBJECTS_PATH, entry.clone(), message);
+
+        if let Some(growth) = behavior.growth {
+            if !has_graphics(item_registry, graphics_desc, growth.into) {
+                problem(format!(
+                    "grows into {:?}, which has no graphics entry",
+                    growth.into
+                ));
+            }
+            if growth.seconds <= 0.0 {
+                problem("growth time is not positive".to_string());
+            }
+        }
+        if let Some(harvest) = behavior.harvest {
+            if !item_registry.items.contains_key(&harvest.item) {
+                problem(format!("harvest item {:?} has no definition", harvest.item));
+            }
+            if harvest.amount == 0 {
+                problem("harvest yields nothing".to_string());
+            }
+            if let Some(leaves) = harvest.leaves {
+                if !has_graphics(item_registry, graphics_desc, leaves) {
+                    problem(format!("leaves {leaves:?}, which has no graphics entry"));
+                }
+            }
+        }
+    }
+}
+
+fn validate_world_gen(
+    report: &mut Report,
+    world_gen: &WorldGenConfig,
+    item_registry: &ItemRegistry,
+    graphics_desc: &GraphicsDescription,
+) {
+    if world_gen.cell_size <= 0.0 || world_gen.biome_scale <= 0.0 || world_gen.chunk_size <= 0.0 {
+        report.push(
+            WORLD_GEN_PATH,
+            "grid",
+            "cell_size, biome_scale and chunk_size have to be positive",
+        );
+    }
+    if world_gen.load_radius < 0 {
+        report.push(WORLD_GEN_PATH, "grid", "load_radius is negative");
+    }
+    if world_gen.landmark_clearance < 0.0 {
+        report.push(WORLD_GEN_PATH, "ground", "landmark_clearance is negative");
+    }
+    if !(0.0..=1.0).contains(&world_gen.water_level) || !(0.0..=0.5).contains(&world_gen.path_width)
+    {
+        report.push(
+            WORLD_GEN_PATH,
+            "ground",
+            "water_level has to be in 0.0..=1.0 and path_width in 0.0..=0.5",
+        );
+    }
+    if world_gen.biomes.is_empty() {
+        report.push(WORLD_GEN_PATH, "biomes", "no biomes defined");
+    }
+    let mut previous_up_to = 0.0;
+    for biome in world_gen.biomes.iter() {
+        let entry = format!("biome {:?}", biome.name);
+        let mut problem = |message: String| report.push(WORLD_GEN_PATH, entry.clone(), message);
+
+        if biome.up_to < previous_up_to || biome.up_to > 1.0 {
+            problem(format!(
+                "up_to {} is not between the previous biome's and 1.0",
+                biome.up_to
+            ));
+        }
+        previous_up_to = biome.up_to;
+        for &(world_object, density) in biome.density.iter() {
+            if !has_graphics(item_registry, graphics_desc, world_object) {
+                problem(format!("{world_object:?} has no graphics entry"));
+            }
+            if density < 0.0 {
+                problem(format!("{world_object:?} has a negative density"));
+            }
+        }
+        let total: f32 = biome.density.iter().map(|&(_, density)| density).sum();
+        if total > 1.0 {
+            problem(format!("densities add up to {total}, more than 1.0"));
+        }
+    }
+    for &(world_object, position) in world_gen.landmarks.iter() {
+        if !has_graphics(item_registry, graphics_desc, world_object) {
+            report.push(
+                WORLD_GEN_PATH,
+                format!("landmark at {position}"),
+                format!("{world_object:?} has no graphics entry"),
+            );
+        }
+    }
+}
+
+fn validate_tiles(report: &mut Report, tile_registry: &TileRegistry, world_gen: &WorldGenConfig) {
+    for (tile, definition) in tile_registry.tiles.iter() {
+        if let Some(message) = rect_problem(&definition.rect) {
+            report.push(TILES_PATH, format!("{tile:?}"), message);
+        }
+    }
+    for name in unit_variants(TileType::type_info()) {
+        if !tile_registry
+            .tiles
+            .keys()
+            .any(|tile| tile.variant_name() == name)
+        {
+            report.push(TILES_PATH, name, "tile has no definition");
+        }
+    }
+
+    if tile_registry.size <= 0.0 {
+        report.push(TILES_PATH, "size", "tile size has to be positive");
+    } else if world_gen.chunk_size % tile_registry.size != 0.0 {
+        report.push(
+            WORLD_GEN_PATH,
+            "grid",
+            format!(
+                "chunk_size {} is not a multiple of the tile size {}",
+                world_gen.chunk_size, tile_registry.size
+            ),
+        );
+    }
+}
+
+/// Items are drawn from their registry entry, everything else from the description.
+fn has_graphics(
+    item_registry: &ItemRegistry,
+    graphics_desc: &GraphicsDescription,
+    world_object: WorldObject,
+) -> bool {
+    match world_object {
+        WorldObject::Item(item) => item_registry.items.contains_key(&item),
+        _ => graphics_desc.map.contains_key(&world_object),
+    }
+}
+
+fn rect_problem(rect: &Rect) -> Option<String> {
+    let inside = rect.min.cmpge(Vec2::ZERO).all() && rect.max.cmple(TEXTURE_SIZE).all();
+    if rect.is_empty() {
+        Some(format!("rect {rect:?} is empty"))
+    } else if !inside {
+        Some(format!(
+            "rect {:?}..{:?} is outside the {}×{} texture",
+            rect.min, rect.max, TEXTURE_SIZE.x, TEXTURE_SIZE.y
+        ))
+    } else {
+        None
+    }
+}
+
+/// Same ingredients and products, regardless of order. Shaped recipes are compared by pattern.
+fn same_recipe(a: &CraftingRecipe, b: &CraftingRecipe) -> bool {
+    a.pattern.is_none()
+        && b.pattern.is_none()
+        && a.needed == b.needed
+        && a.tagged == b.tagged
+        && a.products.len() == b.products.len()
+        && a.products
+            .iter()
+            .all(|product| b.products.contains(product))
+}
+
+/// Names of the variants without fields, leaving out the `None` placeholder.
+fn unit_variants(type_info: &TypeInfo) -> Vec<&'static str> {
+    let TypeInfo::Enum(enum_info) = type_info else {
+        return Vec::new();
+    };
+    enum_info
+        .iter()
+        .filter(|variant| matches!(variant, VariantInfo::Unit(_)))
+        .map(|variant| variant.name())
+        .filter(|&name| name != "None")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Assets {
+        item_registry: ItemRegistry,
+        behaviors: ObjectBehaviors,
+        world_gen: WorldGenConfig,
+        tile_registry: TileRegistry,
+        graphics_desc: GraphicsDescription,
+    }
+
+    /// The shipped descriptions, which the broken books and descriptions are checked against.
+    fn shipped() -> Assets {
+        Assets {
+            item_registry: ItemRegistry::from_path(ITEMS_PATH),
+            behaviors: ObjectBehaviors::from_path(WORLD_OBJECTS_PATH),
+            world_gen: WorldGenConfig::from_path(WORLD_GEN_PATH),
+            tile_registry: TileRegistry::from_path(TILES_PATH),
+            graphics_desc: GraphicsDescription::from_path(&format!("assets/{GRAPHICS_DESC_PATH}")),
+        }
+    }
+
+    impl Assets {
+        fn validate(&self, crafting_book: &CraftingBook) -> Vec<AssetError> {
+            validate(
+                crafting_book,
+                &self.item_registry,
+                &self.behaviors,
+                &self.world_gen,
+                &self.tile_registry,
+                &self.graphics_desc,
+            )
+        }
+    }
+
+    fn book(recipes: &str) -> CraftingBook {
+        ron::de::from_str(&format!("(craftable: [{recipes}])")).unwrap()
+    }
+
+    fn error(file: &'static str, entry: &str, message: &str) -> AssetError {
+        AssetError {
+            file,
+            entry: entry.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn the_shipped_assets_are_valid() {
+        let crafting_book = CraftingBook::from_path(&format!("assets/{CRAFTING_BOOK_PATH}"));
+
+        assert_eq!(shipped().validate(&crafting_book), Vec::new());
+    }
+
+    #[test]
+    fn a_station_without_graphics_is_reported() {
+        let mut assets = shipped();
+        assets.graphics_desc.map.remove(&WorldObject::InventoryBox);
+        let crafting_book = book(
+            r#"(id: "box", needed: {Stone: 1}, products: [(Stones, 1)], station: Some(InventoryBox))"#,
+        );
+
+        assert_eq!(
+            assets.validate(&crafting_book),
+            vec![
+                error(
+                    GRAPHICS_DESC_PATH,
+                    "InventoryBox",
+                    "world object has no graphics entry"
+                ),
+                error(
+                    CRAFTING_BOOK_PATH,
+                    r#"recipe #0 ("box")"#,
+                    "station InventoryBox has no graphics entry"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_world_object_missing_from_the_description_is_reported() {
+        let mut assets = shipped();
+        assets.graphics_desc.map.remove(&WorldObject::Chest);
+
+        assert_eq!(
+            assets.validate(&book("")),
+            vec![error(
+                GRAPHICS_DESC_PATH,
+                "Chest",
+                "world object has no graphics entry"
+            )]
+        );
+    }
+
+    #[test]
+    fn a_zero_quantity_ingredient_is_reported() {
+        let crafting_book = book(r#"(id: "stones", needed: {Stone: 0}, products: [(Stones, 1)])"#);
+
+        assert_eq!(
+            shipped().validate(&crafting_book),
+            vec![error(
+                CRAFTING_BOOK_PATH,
+                r#"recipe #0 ("stones")"#,
+                "ingredient Stone has a quantity of zero"
+            )]
+        );
+    }
+
+    #[test]
+    fn duplicate_recipes_and_ids_are_reported() {
+        let crafting_book = book(
+            r#"
+            (id: "stones", needed: {Stone: 3}, products: [(Stones, 1)]),
+            (id: "more_stones", needed: {Stone: 3}, products: [(Stones, 1)]),
+            (id: "stones", needed: {Stone: 5}, products: [(Stones, 2)]),
+            "#,
+        );
+
+        assert_eq!(
+            shipped().validate(&crafting_book),
+            vec![
+                error(
+                    CRAFTING_BOOK_PATH,
+                    r#"recipe #1 ("more_stones")"#,
+                    "duplicates recipe #0"
+                ),
+                error(
+                    CRAFTING_BOOK_PATH,
+                    r#"recipe #2 ("stones")"#,
+                    "reuses the id of recipe #0"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_rect_outside_the_texture_is_reported() {
+        let mut assets = shipped();
+        assets
+            .graphics_desc
+            .map
+            .get_mut(&WorldObject::Tree)
+            .unwrap()
+            .0 = Rect::new(600.0, 0.0, 700.0, 96.0);
+
+        assert_eq!(
+            assets.validate(&book("")),
+            vec![error(
+                GRAPHICS_DESC_PATH,
+                "Tree",
+                "rect Vec2(600.0, 0.0)..Vec2(700.0, 96.0) is outside the 640×640 texture"
+            )]
+        );
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_once() {
+        let mut assets = shipped();
+        assets.graphics_desc.map.remove(&WorldObject::Chest);
+        assets
+            .graphics_desc
+            .map
+            .get_mut(&WorldObject::Tree)
+            .unwrap()
+            .0 = Rect::new(0.0, 0.0, 0.0, 96.0);
+        let crafting_book = book(
+            r#"
+            (id: "stones", needed: {Stone: 0}, products: [(Stones, 1)]),
+            (id: "stones", needed: {Stone: 3}, products: [(Stones, 1)]),
+            "#,
+        );
+
+        assert_eq!(
+            assets.validate(&crafting_book),
+            vec![
+                error(
+                    GRAPHICS_DESC_PATH,
+                    "Tree",
+                    "rect Rect { min: Vec2(0.0, 0.0), max: Vec2(0.0, 96.0) } is empty"
+                ),
+                error(
+                    GRAPHICS_DESC_PATH,
+                    "Chest",
+                    "world object has no graphics entry"
+                ),
+                error(
+                    CRAFTING_BOOK_PATH,
+                    r#"recipe #0 ("stones")"#,
+                    "ingredient Stone has a quantity of zero"
+                ),
+                error(
+                    CRAFTING_BOOK_PATH,
+                    r#"recipe #1 ("stones")"#,
+                    "reuses the id of recipe #0"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use crate::{
+    npc::NPC_POSITION,
+    tile::{TileRegistry, TileType},
+    validation::load_ron,
+    world_object::WorldObject,
+};
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Deserialize;
+
+pub struct WorldGenPlugin;
+
+impl Plugin for WorldGenPlugin {
+    fn build(&self, app: &mut App) {
+        let config = WorldGenConfig::from_path(WORLD_GEN_PATH);
+        let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        info!("world seed: {seed}");
+        app.insert_resource(config).insert_resource(WorldSeed(seed));
+    }
+}
+
+pub const WORLD_GEN_PATH: &str = "assets/world_gen.ron";
+
+/// Picks everything the generator rolls; the same seed gives the same world.
+#[derive(Resource, Clone, Copy)]
+pub struct WorldSeed(pub u64);
+
+#[derive(Resource, Deserialize)]
+pub struct WorldGenConfig {
+    /// A fresh seed is picked every run when this is `None`.
+    pub seed: Option<u64>,
+    /// Side of the squares the map is generated and streamed in.
+    pub chunk_size: f32,
+    /// Chunks kept around the camera's chunk in every direction.
+    pub load_radius: i32,
+    /// Spacing of the grid objects are scattered on; a cell holds at most one object.
+    pub cell_size: f32,
+    /// Nothing is generated this close to the spawn point.
+    pub spawn_clearance: f32,
+    /// Distance over which the biome noise changes noticeably.
+    pub biome_scale: f32,
+    /// The first biome whose `up_to` is above the noise value wins.
+    pub biomes: Vec<Biome>,
+    /// Ground turns to water where the water noise is below this.
+    pub water_level: f32,
+    /// Paths follow the middle of the path noise, this far either side of 0.5.
+    pub path_width: f32,
+    /// Ground this close to a landmark or the NPC is never water, so they stay in reach.
+    pub landmark_clearance: f32,
+    /// Placed as is, whatever the seed.
+    #[serde(default)]
+    pub landmarks: Vec<(WorldObject, Vec2)>,
+}
+
+#[derive(Deserialize)]
+pub struct Biome {
+    pub name: String,
+    /// Upper bound of the biome noise, in `0.0..=1.0`.
+    pub up_to: f32,
+    /// Tile covering the ground away from water and paths.
+    pub ground: TileType,
+    /// Chance of each object turning up in a cell. They add up to at most 1.
+    pub density: Vec<(WorldObject, f32)>,
+}
+
+/// Seeds of the independent random streams.
+const BIOME_LAYER: u64 = 1;
+const SCATTER_LAYER: u64 = 2;
+const WATER_LAYER: u64 = 3;
+const PATH_LAYER: u64 = 4;
+
+impl WorldGenConfig {
+    pub fn from_path(path: &str) -> Self {
+        load_ron(path)
+    }
+    /// Landmarks and generated objects inside `area`, the min edges included.
+    pub fn populate(
+        &self,
+        seed: u64,
+        area: Rect,
+        tiles: &TileRegistry,
+    ) -> Vec<(WorldObject, Vec2)> {
+        self.landmarks
+            .iter()
+            .copied()
+            .filter(|&(_, position)| {
+                position.cmpge(area.min).all() && position.cmplt(area.max).all()
+            })
+            .chain(self.generate(seed, area, tiles))
+            .collect()
+    }
+    /// Objects for the cells whose corner lies inside `area`. Each cell rolls from its own
+    /// seed, so an area comes out the same no matter what was generated before it.
+    /// Objects only grow on tillable ground.
+    pub fn generate(
+        &self,
+        seed: u64,
+        area: Rect,
+        tiles: &TileRegistry,
+    ) -> Vec<(WorldObject, Vec2)> {
+        let first = (area.min / self.cell_size).ceil().as_ivec2();
+        let last = (area.max / self.cell_size).ceil().as_ivec2();
+
+        let mut objects = Vec::new();
+        for y in first.y..last.y {
+            for x in first.x..last.x {
+                let mut rng = StdRng::seed_from_u64(cell_seed(seed, SCATTER_LAYER, x, y));
+                // keep clear of the cell edges so neighbours don't overlap
+                let offset = Vec2::new(rng.gen_range(0.25..0.75), rng.gen_range(0.25..0.75));
+                let position = (IVec2::new(x, y).as_vec2() + offset) * self.cell_size;
+                let roll = rng.gen::<f32>();
+
+                if position.length() < self.spawn_clearance {
+                    continue;
+                }
+                let ground = self.ground_at(seed, tiles, tiles.coord_at(position));
+                if !tiles.get(ground).tillable {
+                    continue;
+                }
+                let Some(biome) = self.biome_at(seed, position) else {
+                    continue;
+                };
+                let mut chance = 0.0;
+                for &(world_object, density) in biome.density.iter() {
+                    chance += density;
+                    if roll < chance {
+                        objects.push((world_object, position));
+                        break;
+                    }
+                }
+            }
+        }
+        objects
+    }
+    /// The ground of the tile at `coord`, decided at the tile's centre.
+    pub fn ground_at(&self, seed: u64, tiles: &TileRegistry, coord: IVec2) -> TileType {
+        let position = (coord.as_vec2() + 0.5) * tiles.size;
+        let point = position / self.biome_scale;
+        if !self.is_kept_dry(position) && value_noise(seed, WATER_LAYER, point) < self.water_level {
+            return TileType::Water;
+        }
+        if (value_noise(seed, PATH_LAYER, point) - 0.5).abs() < self.path_width {
+            return TileType::Path;
+        }
+        self.biome_at(seed, position)
+            .map_or(TileType::default(), |biome| biome.ground)
+    }
+    /// The spawn area and the surroundings of the landmarks and the NPC are never flooded.
+    pub fn is_kept_dry(&self, position: Vec2) -> bool {
+        position.length() < self.spawn_clearance
+            || self
+                .landmarks
+                .iter()
+                .map(|&(_, landmark)| landmark)
+                .chain([NPC_POSITION])
+                .any(|spot| spot.distance(position) < self.landmark_clearance)
+    }
+    pub fn biome_at(&self, seed: u64, position: Vec2) -> Option<&Biome> {
+        let noise = value_noise(seed, BIOME_LAYER, position / self.biome_scale);
+        self.biomes
+            .iter()
+            .find(|biome| noise < biome.up_to)
+            .or(self.biomes.last())
+    }
+}
+
+/// Smoothly interpolated random values on an integer lattice, in `0.0..1.0`.
+fn value_noise(seed: u64, layer: u64, point: Vec2) -> f32 {
+    let corner = point.floor();
+    let t = point - corner;
+    let t = t * t * (Vec2::splat(3.0) - 2.0 * t);
+    let (x, y) = (corner.x as i32, corner.y as i32);
+    let lattice =
+        |x: i32, y: i32| (cell_seed(seed, layer, x, y) >> 40) as f32 / (1u64 << 24) as f32;
+
+    let bottom = lattice(x, y) + (lattice(x + 1, y) - lattice(x, y)) * t.x;
+    let top = lattice(x, y + 1) + (lattice(x + 1, y + 1) - lattice(x, y + 1)) * t.x;
+    bottom + (top - bottom) * t.y
+}
+
+/// Mixes the world seed with a grid position into the seed of that position's stream.
+fn cell_seed(seed: u64, layer: u64, x: i32, y: i32) -> u64 {
+    let position = ((x as u32 as u64) << 32) | y as u32 as u64;
+    splitmix64(splitmix64(seed ^ layer) ^ position)
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WorldGenConfig {
+        ron::de::from_str(
+            r#"(
+                seed: None,
+                chunk_size: 256.0,
+                load_radius: 1,
+                cell_size: 32.0,
+                spawn_clearance: 100.0,
+                biome_scale: 300.0,
+                water_level: 0.2,
+                path_width: 0.03,
+                landmark_clearance: 96.0,
+                biomes: [
+                    Biome(name: "meadow", ground: Grass, up_to: 0.5, density: [(Grass, 0.3), (Item(Stone), 0.1)]),
+                    Biome(name: "forest", ground: Dirt, up_to: 1.0, density: [(Tree, 0.4), (Trunk, 0.1)]),
+                ],
+                landmarks: [(Item(Scroll), Vec2(-120.0, -80.0))],
+            )"#,
+        )
+        .unwrap()
+    }
+
+    fn tiles() -> TileRegistry {
+        let rect = "rect: Rect(min: Vec2(0.0, 0.0), max: Vec2(16.0, 16.0))";
+        ron::de::from_str(&format!(
+            "(size: 32.0, tiles: {{
+                Grass: TileDefinition({rect}, walkable: true, tillable: true),
+                Dirt: TileDefinition({rect}, walkable: true, tillable: true),
+                Water: TileDefinition({rect}, walkable: false, tillable: false),
+                Path: TileDefinition({rect}, walkable: true, tillable: false),
+            }})"
+        ))
+        .unwrap()
+    }
+
+    fn sorted(mut objects: Vec<(WorldObject, Vec2)>) -> Vec<(WorldObject, Vec2)> {
+        objects.sort_by(|(_, a), (_, b)| (a.x, a.y).partial_cmp(&(b.x, b.y)).unwrap());
+        objects
+    }
+
+    fn area() -> Rect {
+        Rect::new(-768.0, -768.0, 768.0, 768.0)
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_world() {
+        let (config, tiles) = (config(), tiles());
+
+        let first = config.populate(42, area(), &tiles);
+        assert!(!first.is_empty());
+        assert_eq!(first, config.populate(42, area(), &tiles));
+        assert_ne!(first, config.populate(43, area(), &tiles));
+    }
+
+    #[test]
+    fn chunks_add_up_to_the_whole_area() {
+        let (config, tiles) = (config(), tiles());
+
+        let whole = config.populate(7, area(), &tiles);
+        let first = config.chunk_at(area().min);
+        let last = config.chunk_at(area().max - Vec2::ONE);
+        let mut chunked = Vec::new();
+        for y in first.y..=last.y {
+            for x in first.x..=last.x {
+                let chunk = config.chunk_area(IVec2::new(x, y));
+                chunked.extend(config.populate(7, chunk, &tiles));
+            }
+        }
+
+        assert_eq!(sorted(chunked), sorted(whole));
+    }
+
+    /// Tiles whose centre lies within `radius` of `center`.
+    fn tiles_around(tiles: &TileRegistry, center: Vec2, radius: f32) -> Vec<IVec2> {
+        let first = tiles.coord_at(center - radius);
+        let last = tiles.coord_at(center + radius);
+        (first.y..=last.y)
+            .flat_map(|y| (first.x..=last.x).map(move |x| IVec2::new(x, y)))
+            .filter(|coord| ((coord.as_vec2() + 0.5) * tiles.size).distance(center) < radius)
+            .collect()
+    }
+
+    #[test]
+    fn the_spawn_area_stays_clear_and_dry() {
+        let (config, tiles) = (config(), tiles());
+
+        for seed in 0..8 {
+            for (world_object, position) in config.generate(seed, area(), &tiles) {
+                assert!(
+                    position.length() >= config.spawn_clearance,
+                    "{world_object:?} generated at {position}"
+                );
+            }
+            for coord in tiles_around(&tiles, Vec2::ZERO, config.spawn_clearance) {
+                assert_ne!(
+                    config.ground_at(seed, &tiles, coord),
+                    TileType::Water,
+                    "seed {seed} floods the spawn tile {coord}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn landmarks_and_the_npc_stay_on_walkable_ground() {
+        let (config, tiles) = (config(), tiles());
+        let spots = config
+            .landmarks
+            .iter()
+            .map(|&(_, position)| position)
+            .chain([NPC_POSITION])
+            .collect::<Vec<_>>();
+
+        for seed in 0..32 {
+            for &spot in spots.iter() {
+                for coord in tiles_around(&tiles, spot, config.landmark_clearance) {
+                    let ground = config.ground_at(seed, &tiles, coord);
+                    assert!(
+                        tiles.get(ground).walkable,
+                        "seed {seed} puts {ground:?} at {coord} next to {spot}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn objects_only_grow_on_tillable_ground() {
+        let (config, tiles) = (config(), tiles());
+
+        for (world_object, position) in config.generate(3, area(), &tiles) {
+            let ground = config.ground_at(3, &tiles, tiles.coord_at(position));
+            assert!(
+                tiles.get(ground).tillable,
+                "{world_object:?} generated on {ground:?} at {position}"
+            );
+        }
+    }
+}
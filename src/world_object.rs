@@ -1,5 +1,11 @@
-use crate::graphics::Graphics;
-use bevy::prelude::*;
+use crate::{
+    chest::{Chest, CHEST_CAPACITY},
+    graphics::Graphics,
+    inventory::Inventory,
+    item::ItemRegistry,
+    validation::load_ron,
+};
+use bevy::{prelude::*, utils::HashMap};
 use bevy_inspector_egui::InspectorOptions;
 use serde::Deserialize;
 
@@ -7,7 +13,7 @@ pub struct WorldObjectPlugin;
 
 impl Plugin for WorldObjectPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_world_objects_system)
+        app.insert_resource(ObjectBehaviors::from_path(WORLD_OBJECTS_PATH))
             .add_systems(Update, update_world_objects_graphics_system)
             .add_systems(Update, regrowth_system);
     }
@@ -35,14 +41,80 @@ pub enum WorldObject {
     GrassWithFlower,
     Grass,
     InventoryBox,
+    Chest,
+    Workbench,
 }
 
+pub const WORLD_OBJECTS_PATH: &str = "assets/world_objects.ron";
+
 #[derive(Component, InspectorOptions, Reflect)]
 pub struct Pickupable {
     pub item: ItemType,
+    pub amount: usize,
     pub drops: Option<WorldObject>,
 }
 
+/// How each world object grows and what harvesting it gives.
+#[derive(Resource, Deserialize)]
+pub struct ObjectBehaviors {
+    pub behaviors: HashMap<WorldObject, ObjectBehavior>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct ObjectBehavior {
+    #[serde(default)]
+    pub growth: Option<Growth>,
+    #[serde(default)]
+    pub harvest: Option<Harvest>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct Growth {
+    pub into: WorldObject,
+    pub seconds: f32,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct Harvest {
+    pub item: ItemType,
+    #[serde(default = "one")]
+    pub amount: usize,
+    /// What stays behind; the object is gone when this is `None`.
+    #[serde(default)]
+    pub leaves: Option<WorldObject>,
+}
+
+fn one() -> usize {
+    1
+}
+
+impl ObjectBehaviors {
+    pub fn from_path(path: &str) -> Self {
+        load_ron(path)
+    }
+    pub fn growth(&self, world_object: WorldObject) -> Option<Growth> {
+        self.behaviors
+            .get(&world_object)
+            .and_then(|behavior| behavior.growth)
+    }
+    /// Items lying around are picked up whole; everything else per its harvest rule.
+    pub fn pickupable(&self, world_object: WorldObject) -> Option<Pickupable> {
+        if let WorldObject::Item(item) = world_object {
+            return Some(Pickupable {
+                item,
+                amount: 1,
+                drops: None,
+            });
+        }
+        let harvest = self.behaviors.get(&world_object)?.harvest?;
+        Some(Pickupable {
+            item: harvest.item,
+            amount: harvest.amount,
+            drops: harvest.leaves,
+        })
+    }
+}
+
 #[derive(
     Deserialize,
     Component,
@@ -53,6 +125,8 @@ pub struct Pickupable {
     Hash,
     PartialEq,
     Eq,
+    PartialOrd,
+    Ord,
     InspectorOptions,
     Reflect,
 )]
@@ -65,37 +139,38 @@ pub enum ItemType {
     Axe,
     Wood,
     Fire,
+    Chest,
+    Workbench,
+    Scroll,
 }
 
 #[derive(Component, Reflect, Default)]
 #[reflect(Component)]
 pub struct ReGrowthTimer(pub Timer);
 
-impl From<WorldObject> for String {
-    fn from(val: WorldObject) -> Self {
-        match val {
+impl WorldObject {
+    pub fn display_name(&self, item_registry: &ItemRegistry) -> String {
+        match self {
             WorldObject::None => "None".to_string(),
-            WorldObject::Item(item_type) => format!("Item {item_type:?}"),
+            WorldObject::Item(item_type) => {
+                let definition = item_registry.get(*item_type);
+                format!("{:?} {}", definition.category, definition.name)
+            }
             WorldObject::Tree => "Tree".to_string(),
             WorldObject::Trunk => "Sapling".to_string(),
             WorldObject::GrassWithFlower => "grass with flower".to_string(),
             WorldObject::Grass => "grass without flower".to_string(),
             WorldObject::InventoryBox => "inventory box".to_string(),
+            WorldObject::Chest => "chest".to_string(),
+            WorldObject::Workbench => "workbench".to_string(),
         }
     }
-}
-
-impl ItemType {
-    pub fn is_draggable(&self) -> bool {
-        matches!(self, ItemType::Fire)
-    }
-}
-
-impl WorldObject {
     pub fn spawn(
         self,
         commands: &mut Commands,
         graphics: &Graphics,
+        item_registry: &ItemRegistry,
+        behaviors: &ObjectBehaviors,
         custom_size: Option<Vec2>,
         position: Option<Vec2>,
     ) -> Entity {
@@ -116,62 +191,46 @@ impl WorldObject {
             Transform::from_translation(position.unwrap_or(Vec2::ZERO).extend(0.0));
         sprite_sheet.sprite.custom_size = custom_size.or(Some(size));
 
-        let mut ent = commands.spawn((sprite_sheet, Name::new(String::from(self))));
+        let mut ent = commands.spawn((
+            sprite_sheet,
+            self,
+            Name::new(self.display_name(item_registry)),
+        ));
 
-        if let Some(pickable) = self.pickupable_into() {
+        if let Some(pickable) = behaviors.pickupable(self) {
             ent.insert(pickable);
         }
-        ent.id()
-    }
-    pub fn growth_into(&self) -> Option<(WorldObject, f32)> {
-        match self {
-            WorldObject::Trunk => Some((WorldObject::Tree, 4.0)),
-            WorldObject::Grass => Some((WorldObject::GrassWithFlower, 2.0)),
-            _ => None,
-        }
-    }
-    pub fn pickupable_into(&self) -> Option<Pickupable> {
-        match self {
-            WorldObject::Item(item_type) => Some(Pickupable {
-                item: *item_type,
-                drops: None,
-            }),
-            WorldObject::Tree => Some(Pickupable {
-                item: ItemType::Wood,
-                drops: Some(WorldObject::Trunk),
-            }),
-            WorldObject::GrassWithFlower => Some(Pickupable {
-                item: ItemType::Flower,
-                drops: Some(WorldObject::Grass),
-            }),
-            _ => None,
+        if self == WorldObject::Chest {
+            ent.insert((Chest, Inventory::with_capacity(CHEST_CAPACITY)));
         }
+        ent.id()
     }
 }
 
 pub fn regrowth_system(
     mut commands: Commands,
     mut world_obj_query: Query<(Entity, &mut WorldObject, Option<&mut ReGrowthTimer>)>,
+    behaviors: Res<ObjectBehaviors>,
     time: Res<Time>,
 ) {
     for (ent, mut world_obj, growth_timer) in world_obj_query.iter_mut() {
-        if let Some((growth_into, growth_time)) = world_obj.growth_into() {
+        if let Some(growth) = behaviors.growth(*world_obj) {
             match growth_timer {
                 Some(mut timer) => {
                     timer.0.tick(time.delta());
                     if timer.0.finished() {
                         commands.entity(ent).remove::<ReGrowthTimer>();
-                        if let Some(pickupable) = growth_into.pickupable_into() {
+                        if let Some(pickupable) = behaviors.pickupable(growth.into) {
                             commands.entity(ent).insert(pickupable);
                         }
-                        *world_obj = growth_into;
+                        *world_obj = growth.into;
                     }
                 }
                 None => {
                     commands
                         .entity(ent)
                         .insert(ReGrowthTimer(Timer::from_seconds(
-                            growth_time,
+                            growth.seconds,
                             TimerMode::Once,
                         )));
                 }
@@ -180,68 +239,17 @@ pub fn regrowth_system(
     }
 }
 
-pub fn spawn_world_objects_system(mut commands: Commands, graphics: Res<Graphics>) {
-    let world_objects = vec![
-        WorldObject::Item(ItemType::Stone).spawn(
-            &mut commands,
-            &graphics,
-            None,
-            Some(Vec2::new(40.0, 50.0)),
-        ),
-        WorldObject::Item(ItemType::Stone).spawn(
-            &mut commands,
-            &graphics,
-            None,
-            Some(Vec2::new(-40.0, 30.0)),
-        ),
-        WorldObject::Item(ItemType::Stone).spawn(
-            &mut commands,
-            &graphics,
-            None,
-            Some(Vec2::new(120.0, -50.0)),
-        ),
-        WorldObject::Trunk.spawn(
-            &mut commands,
-            &graphics,
-            None,
-            Some(Vec2::new(120.0, -90.0)),
-        ),
-        WorldObject::Tree.spawn(
-            &mut commands,
-            &graphics,
-            Some(Vec2::new(64.0, 96.0)),
-            Some(Vec2::new(420.0, -50.0)),
-        ),
-        WorldObject::Tree.spawn(
-            &mut commands,
-            &graphics,
-            Some(Vec2::new(64.0, 96.0)),
-            Some(Vec2::new(280.0, -60.0)),
-        ),
-        WorldObject::GrassWithFlower.spawn(
-            &mut commands,
-            &graphics,
-            None,
-            Some(Vec2::new(180.0, -60.0)),
-        ),
-    ];
-
-    commands
-        .spawn(SpatialBundle::default())
-        .push_children(&world_objects);
-}
-
 pub fn update_world_objects_graphics_system(
-    mut world_obj_query: Query<
-        (&WorldObject, &mut TextureAtlasSprite, &mut Transform),
-        Changed<WorldObject>,
-    >,
+    mut world_obj_query: Query<(Ref<WorldObject>, &mut TextureAtlasSprite, &mut Transform)>,
     graphics: Res<Graphics>,
 ) {
     for (world_object, mut sprite, mut transform) in world_obj_query.iter_mut() {
+        if !world_object.is_changed() && !graphics.is_changed() {
+            continue;
+        }
         let (index, size) = *graphics
             .item_index_map
-            .get(world_object)
+            .get(&*world_object)
             .unwrap_or_else(|| panic!("world object index not found: {:?}", world_object));
         sprite.index = index;
         if let Some(old_size) = sprite.custom_size {